@@ -0,0 +1,284 @@
+use std::fmt::{self, Display};
+
+use clap::ValueEnum;
+
+use crate::multiplier::Multiplier;
+use crate::Element;
+use crate::Matrix;
+use crate::Result;
+use crate::{BasicMultiplier, CpuMultiplier};
+
+/// Difference between a result and the expected one, computed in `f64`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Accuracy {
+    /// Largest absolute difference of two elements
+    pub max_abs_error: f64,
+    /// Largest absolute difference of two elements relative to the expected one, infinite if an
+    /// element that is expected to be zero is not
+    pub max_rel_error: f64,
+    /// Largest distance of two elements in representable values of the element type, see
+    /// [Element::ulps]
+    pub max_ulps: u64,
+    /// Frobenius norm of the difference relative to the one of the expected result
+    pub rel_error: f64,
+    /// Element with the largest absolute difference, `None` for empty matrices
+    pub worst: Option<ElementError>,
+}
+
+/// Position and values of a single element of [Accuracy]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ElementError {
+    pub row: usize,
+    pub col: usize,
+    pub actual: f64,
+    pub expected: f64,
+}
+
+impl Accuracy {
+    /// Compares two matrices of the same shape, `expected` can have a more precise element type,
+    /// it is rounded to `T` only to count ulps
+    pub(crate) fn compare<T: Element, U: Element>(
+        actual: &Matrix<T>,
+        expected: &Matrix<U>,
+    ) -> Self {
+        let mut max_abs_error = 0.0f64;
+        let mut max_rel_error = 0.0f64;
+        let mut max_ulps = 0;
+        let mut worst: Option<ElementError> = None;
+        let mut diff_norm = 0.0;
+        let mut norm = 0.0;
+        let cols = actual.cols;
+
+        for (index, (&actual, &expected)) in actual.iter().zip(expected.iter()).enumerate() {
+            let ulps = actual.ulps(T::from_f64(expected.to_f64()));
+            let (actual, expected) = (actual.to_f64(), expected.to_f64());
+            let diff = if actual == expected {
+                0.0
+            } else {
+                // NaN differences are as bad as it gets
+                let diff = (actual - expected).abs();
+                if diff.is_nan() {
+                    f64::INFINITY
+                } else {
+                    diff
+                }
+            };
+            let rel = if diff == 0.0 {
+                0.0
+            } else {
+                diff / expected.abs()
+            };
+
+            if worst.is_none() || diff > max_abs_error {
+                worst = Some(ElementError {
+                    row: index / cols,
+                    col: index % cols,
+                    actual,
+                    expected,
+                });
+            }
+            max_abs_error = max_abs_error.max(diff);
+            max_rel_error = max_rel_error.max(rel);
+            max_ulps = max_ulps.max(ulps);
+            diff_norm += diff * diff;
+            norm += expected * expected;
+        }
+
+        let rel_error = if norm == 0.0 {
+            diff_norm.sqrt()
+        } else {
+            (diff_norm / norm).sqrt()
+        };
+
+        Self {
+            max_abs_error,
+            max_rel_error,
+            max_ulps,
+            rel_error,
+            worst,
+        }
+    }
+}
+
+/// Elements of two matrices that are not within tolerances of each other, see [Matrix::diff]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatrixDiff {
+    /// Metrics of every element, the second matrix is the expected one
+    pub accuracy: Accuracy,
+    /// Elements that are not within the tolerances, in row major order
+    pub mismatches: Vec<ElementError>,
+    /// Number of compared elements
+    pub len: usize,
+    /// `(rows, cols)` of both matrices if they differ, no elements are compared then
+    pub shapes: Option<((usize, usize), (usize, usize))>,
+}
+
+impl MatrixDiff {
+    /// Whether the shapes match and every element is within the tolerances
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_none() && self.mismatches.is_empty()
+    }
+}
+
+/// Number of mismatches that [MatrixDiff] displays, so that assertion messages stay readable
+const SHOWN_MISMATCHES: usize = 10;
+
+impl Display for MatrixDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((actual, expected)) = self.shapes {
+            return write!(
+                f,
+                "shapes differ, {}x{} instead of {}x{}",
+                actual.0, actual.1, expected.0, expected.1
+            );
+        }
+
+        write!(
+            f,
+            "{} of {} elements differ, max absolute error {:e}, max relative error {:e}",
+            self.mismatches.len(),
+            self.len,
+            self.accuracy.max_abs_error,
+            self.accuracy.max_rel_error
+        )?;
+
+        for el in self.mismatches.iter().take(SHOWN_MISMATCHES) {
+            write!(
+                f,
+                "\n  row {}, column {}: {} instead of {}",
+                el.row, el.col, el.actual, el.expected
+            )?;
+        }
+
+        if self.mismatches.len() > SHOWN_MISMATCHES {
+            write!(f, "\n  ...")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Product that [verify] compares results against
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum Reference {
+    /// [BasicMultiplier] in the element type, rounds like a naive loop would
+    Basic,
+    /// [CpuMultiplier] on the operands converted to `f64`, closer to the exact product for every
+    /// element type but `f64`
+    #[default]
+    F64,
+}
+
+/// Bounds that the metrics of an [Accuracy] must not exceed, `None` bounds are not checked
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Tolerance {
+    pub max_abs_error: Option<f64>,
+    pub max_rel_error: Option<f64>,
+    pub max_ulps: Option<u64>,
+    pub rel_error: Option<f64>,
+}
+
+impl Tolerance {
+    /// Bound on the Frobenius relative error of a product with inner dimension `k`
+    ///
+    /// Rounding errors of a sum of `k` products grow like `sqrt(k)` epsilons of its largest
+    /// partial sum, four times that much is allowed so that products summed in different orders
+    /// still pass. Integer products must be exact. [Matrix::product_tolerance] is the same bound
+    /// for single elements
+    pub fn for_product<T: Element>(k: usize) -> Self {
+        Self {
+            rel_error: Some(4.0 * (k.max(1) as f64).sqrt() * T::EPSILON),
+            ..Self::default()
+        }
+    }
+
+    /// Metrics of `accuracy` that exceed their bound, as `(name, value, bound)`
+    pub fn violations(&self, accuracy: &Accuracy) -> Vec<(&'static str, f64, f64)> {
+        let bounds = [
+            (
+                "max absolute error",
+                accuracy.max_abs_error,
+                self.max_abs_error,
+            ),
+            (
+                "max relative error",
+                accuracy.max_rel_error,
+                self.max_rel_error,
+            ),
+            (
+                "max ulp distance",
+                accuracy.max_ulps as f64,
+                self.max_ulps.map(|ulps| ulps as f64),
+            ),
+            ("relative error", accuracy.rel_error, self.rel_error),
+        ];
+
+        bounds
+            .into_iter()
+            .filter_map(|(name, value, bound)| {
+                bound
+                    .filter(|&bound| value > bound || value.is_nan())
+                    .map(|bound| (name, value, bound))
+            })
+            .collect()
+    }
+}
+
+/// Outcome of [verify]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Verification {
+    pub accuracy: Accuracy,
+    pub tolerance: Tolerance,
+}
+
+impl Verification {
+    /// Whether every metric is within its bound
+    pub fn passed(&self) -> bool {
+        self.tolerance.violations(&self.accuracy).is_empty()
+    }
+}
+
+/// Multiplies `a` and `b` with `multiplier` and with `reference`, and checks the difference of
+/// the results against `tolerance`
+///
+/// Fails if either multiplication does, for example when the dimensions do not match
+///
+/// ```
+/// use matmul::{verify, BasicMultiplier, Matrix, Reference, Tolerance};
+///
+/// let a = Matrix::create(2, 2, &[1.0f32, 2.0, 3.0, 4.0]).unwrap();
+/// let b = Matrix::create(2, 2, &[4.0f32, 3.0, 2.0, 1.0]).unwrap();
+///
+/// let mut multiplier = BasicMultiplier::default();
+/// let tolerance = Tolerance::for_product::<f32>(2);
+/// let res = verify(&mut multiplier, &a, &b, Reference::F64, tolerance).unwrap();
+///
+/// assert!(res.passed());
+/// assert_eq!(res.accuracy.max_ulps, 0);
+/// ```
+pub fn verify<T: Element>(
+    multiplier: &mut dyn Multiplier<T>,
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+    reference: Reference,
+    tolerance: Tolerance,
+) -> Result<Verification> {
+    let actual = multiplier.multiply(a, b)?;
+
+    let accuracy = match reference {
+        Reference::Basic => {
+            let expected = BasicMultiplier::default().multiply(a, b)?;
+            Accuracy::compare(&actual, &expected)
+        }
+        Reference::F64 => {
+            let expected = CpuMultiplier::new(None).multiply(&a.convert::<f64>(), &b.convert())?;
+            Accuracy::compare(&actual, &expected)
+        }
+    };
+
+    Ok(Verification {
+        accuracy,
+        tolerance,
+    })
+}
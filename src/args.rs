@@ -1,51 +1,108 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
 
-#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
-#[clap(rename_all = "lowercase")]
-pub enum DeviceType {
-    /// Dedicated gpu
-    DGpu = 0,
-    /// Integrated gpu
-    IGpu = 1,
-    /// Any gpu
-    Gpu = 2,
-    /// Any cpu
-    Cpu = 3,
-    /// Any device found
-    #[default]
-    All = 4,
-}
+use matmul::{ElementType, FileFormat, Mode};
 
-/// 4 implementations are provided as of time of writing
-#[derive(Debug, Subcommand)]
-pub enum Mode {
-    /// Basic implementation is just 3 loops on the host
-    Basic,
-    /// Easy implementation is a naive implementation that uses the gpu
-    Easy {
-        device_type: Option<DeviceType>,
-        index: Option<usize>,
-    },
-    /// Medium implementation is a less naive implementation that uses local memory on the gpu
-    Medium {
-        device_type: Option<DeviceType>,
-        index: Option<usize>,
-    },
-    /// Hard is an okay implementation that optimized thread throughput
-    Hard {
-        device_type: Option<DeviceType>,
-        index: Option<usize>,
-    },
-}
+use crate::bench::BenchArgs;
+use crate::generate::GenerateArgs;
+use crate::tune::TuneArgs;
+use crate::verify::VerifyArgs;
 
 #[derive(Debug, Parser)]
 #[command(about = "Matrix multiplication on the GPU", long_about = None)]
+#[command(allow_missing_positional = true, subcommand_negates_reqs = true)]
+#[command(override_usage = "rust-matmul [OPTIONS] [INPUT] <OUTPUT> <COMMAND>
+       rust-matmul devices [--json]
+       rust-matmul [OPTIONS] bench [BENCH OPTIONS]
+       rust-matmul [OPTIONS] generate --shape <SHAPE> <OUTPUT>
+       rust-matmul [OPTIONS] verify [VERIFY OPTIONS] <COMMAND>
+       rust-matmul [OPTIONS] tune [TUNE OPTIONS]")]
 pub struct Args {
     /// Input file with the matrices that are to be multiplied
-    pub input: String,
+    #[arg(required_unless_present_all = ["a", "b"], conflicts_with_all = ["a", "b"])]
+    pub input: Option<String>,
     /// Output file where the result of the multiplication will be
-    pub output: String,
+    #[arg(required = true)]
+    pub output: Option<String>,
+    /// File with the left matrix, instead of an input file with both
+    #[arg(short, long, requires = "b")]
+    pub a: Option<String>,
+    /// File with the right matrix, instead of an input file with both
+    #[arg(short, long, requires = "a")]
+    pub b: Option<String>,
+    /// Delimiter of CSV files given with `--a` and `--b`, detected by default
+    #[arg(long)]
+    pub delimiter: Option<char>,
+    /// Whether CSV files given with `--a` and `--b` start with a header row, detected by default
+    #[arg(long)]
+    pub header: Option<bool>,
+    /// Format of the output file, the format of the input file is detected
+    #[arg(short = 'f', long, value_enum, default_value_t)]
+    pub output_format: FileFormat,
+    /// Type of the matrix elements
+    #[arg(short, long, value_enum, default_value_t)]
+    pub dtype: ElementType,
+    /// Build OpenCl programs from source without using or updating the program cache
+    #[arg(long)]
+    pub no_cache: bool,
+    /// Remove every cached OpenCl program before multiplying
+    #[arg(long)]
+    pub clear_cache: bool,
+    /// Build the hard implementation with the default kernel config instead of the one stored by
+    /// the tune command
+    #[arg(long)]
+    pub no_tuning: bool,
     /// Choose where to multiply the matrices
     #[command(subcommand)]
-    pub mode: Mode,
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    #[command(flatten)]
+    Multiply(Mode),
+    /// List every OpenCl platform and device with the indices that the OpenCl implementations
+    /// expect, no input or output files are needed
+    Devices {
+        /// Print the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Time every implementation given with `--modes` on random matrices of every size given with
+    /// `--sizes`, no input or output files are needed
+    Bench(BenchArgs),
+    /// Write random matrices of a chosen shape and distribution to a file, a single matrix or an
+    /// input file with both operands
+    Generate(GenerateArgs),
+    /// Multiply random matrices, or the ones given with `--a` and `--b`, with an implementation
+    /// and with a reference, and check that the results are close enough
+    Verify(VerifyArgs),
+    /// Time every kernel config of the hard implementation on a device and store the fastest one,
+    /// which the hard implementation then uses on that device
+    Tune(TuneArgs),
+}
+
+impl Args {
+    /// Exits with a usage error unless there are input and output files
+    ///
+    /// Clap can not check them because the devices, bench, generate, verify and tune commands need neither
+    pub fn require_files(&self) {
+        let mut missing = vec![];
+        if self.input.is_none() && (self.a.is_none() || self.b.is_none()) {
+            missing.push("<INPUT> or --a <A> --b <B>");
+        }
+        if self.output.is_none() {
+            missing.push("<OUTPUT>");
+        }
+
+        if !missing.is_empty() {
+            let message = format!(
+                "the following required arguments were not provided:\n  {}",
+                missing.join("\n  ")
+            );
+            Self::command()
+                .error(ErrorKind::MissingRequiredArgument, message)
+                .exit();
+        }
+    }
 }
@@ -0,0 +1,348 @@
+use std::fmt::{self, Display};
+use std::mem;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::time;
+
+use clap::ValueEnum;
+use matmul::implementation_with_options;
+use matmul::{DeviceSelector, Kernel, Leaf, Mode};
+use matmul::{Distribution, Element, ElementType, Generator, Matrix, Options};
+
+use crate::json::{array, number, object, string};
+
+/// Options of the bench command
+#[derive(Debug, clap::Args)]
+pub struct BenchArgs {
+    /// Comma separated sizes, `n` for square matrices or `MxKxN` for an `M x K` by `K x N` product
+    #[arg(short, long, value_delimiter = ',', default_value = "256,512,1024")]
+    pub sizes: Vec<Size>,
+    /// Comma separated implementations to compare
+    #[arg(
+        short,
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "easy,medium,hard"
+    )]
+    pub modes: Vec<Implementation>,
+    /// Untimed runs of every size and implementation before the timed ones
+    #[arg(short, long, default_value_t = 2)]
+    pub warmup: usize,
+    /// Timed runs of every size and implementation
+    #[arg(short, long, default_value = "10")]
+    pub repetitions: NonZeroUsize,
+    /// How the results are printed
+    #[arg(long, value_enum, default_value_t)]
+    pub format: ReportFormat,
+    #[command(flatten)]
+    pub device: DeviceSelector,
+}
+
+/// Shape of a benchmarked product, `m x k` by `k x n`
+#[derive(Copy, Clone, Debug)]
+pub struct Size {
+    pub m: usize,
+    pub k: usize,
+    pub n: usize,
+}
+
+impl FromStr for Size {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let dims = s
+            .split('x')
+            .map(|dim| dim.trim().parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid size `{s}`, {e}"))?;
+
+        match dims[..] {
+            [n] => Ok(Self { m: n, k: n, n }),
+            [m, k, n] => Ok(Self { m, k, n }),
+            _ => Err(format!("invalid size `{s}`, expected `n` or `MxKxN`")),
+        }
+    }
+}
+
+impl Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.m == self.k && self.k == self.n {
+            write!(f, "{}", self.n)
+        } else {
+            write!(f, "{}x{}x{}", self.m, self.k, self.n)
+        }
+    }
+}
+
+/// Implementations that can be benchmarked, each with its default options
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum Implementation {
+    Basic,
+    Cpu,
+    Simd,
+    Strassen,
+    Easy,
+    Medium,
+    Hard,
+    Multi,
+}
+
+impl Implementation {
+    /// Mode of the implementation, OpenCl ones use the devices picked by `device`
+    fn mode(self, device: DeviceSelector) -> Mode {
+        match self {
+            Self::Basic => Mode::Basic,
+            Self::Cpu => Mode::Cpu { threads: None },
+            Self::Simd => Mode::Simd { threads: None },
+            Self::Strassen => Mode::Strassen {
+                leaf: Leaf::default(),
+                crossover: 256,
+                accuracy: false,
+                device,
+            },
+            Self::Easy => Mode::Easy { device },
+            Self::Medium => Mode::Medium { device },
+            Self::Hard => Mode::Hard { device },
+            Self::Multi => Mode::Multi {
+                kernel: Kernel::default(),
+                device,
+            },
+        }
+    }
+}
+
+impl Display for Implementation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // unwrap is safe because no variant is skipped
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// How [run] prints the results
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum ReportFormat {
+    /// Aligned columns for reading
+    #[default]
+    Table,
+    Csv,
+    Json,
+}
+
+/// Timings of one implementation on one size
+struct Report {
+    size: Size,
+    implementation: Implementation,
+    /// Times of the timed runs in milliseconds, sorted
+    times: Vec<f64>,
+    /// Bytes of both operands and the result
+    bytes: usize,
+}
+
+impl Report {
+    fn min(&self) -> f64 {
+        self.times[0]
+    }
+
+    fn median(&self) -> f64 {
+        let mid = self.times.len() / 2;
+        if self.times.len().is_multiple_of(2) {
+            (self.times[mid - 1] + self.times[mid]) / 2.0
+        } else {
+            self.times[mid]
+        }
+    }
+
+    /// Smallest time that at least 95% of the runs did not exceed
+    fn p95(&self) -> f64 {
+        let rank = (self.times.len() as f64 * 0.95).ceil() as usize;
+        self.times[rank.max(1) - 1]
+    }
+
+    /// Sample standard deviation, zero for a single run
+    fn stddev(&self) -> f64 {
+        let count = self.times.len() as f64;
+        if self.times.len() < 2 {
+            return 0.0;
+        }
+
+        let mean = self.times.iter().sum::<f64>() / count;
+        let sum = self
+            .times
+            .iter()
+            .map(|time| (time - mean) * (time - mean))
+            .sum::<f64>();
+
+        (sum / (count - 1.0)).sqrt()
+    }
+
+    /// Billions of floating point operations per second at the median time, a multiply and an add
+    /// per inner product step
+    fn gflops(&self) -> f64 {
+        let Size { m, k, n } = self.size;
+        2.0 * (m * k * n) as f64 / self.median() / 1e6
+    }
+
+    /// Gigabytes per second at the median time, if every operand and the result cross the memory
+    /// bus once
+    fn bandwidth(&self) -> f64 {
+        self.bytes as f64 / self.median() / 1e6
+    }
+}
+
+/// Multiplies random matrices of every size with every implementation and prints the timings
+pub fn run<T: Element>(args: &BenchArgs, options: &Options) {
+    let mut reports = vec![];
+    let mut generator = Generator::new(rand::random());
+
+    for &size in &args.sizes {
+        let a = random::<T>(&mut generator, size.m, size.k);
+        let b = random::<T>(&mut generator, size.k, size.n);
+
+        for &implementation in &args.modes {
+            let mode = implementation.mode(args.device.clone());
+            let mut multiplier = match implementation_with_options::<T>(mode, options) {
+                Ok(res) => res,
+                Err(e) => {
+                    eprintln!("unable to create {implementation} multiplier: {e}");
+                    continue;
+                }
+            };
+
+            let mut times = vec![];
+            let runs = args.warmup + args.repetitions.get();
+            let res = (0..runs).try_for_each(|run| {
+                let instant = time::Instant::now();
+                multiplier.multiply(&a, &b)?;
+                if run >= args.warmup {
+                    times.push(instant.elapsed().as_secs_f64() * 1e3);
+                }
+                Ok::<_, matmul::MatmulError>(())
+            });
+
+            if let Err(e) = res {
+                eprintln!("unable to multiply {size} with {implementation}: {e}");
+                continue;
+            }
+
+            times.sort_by(f64::total_cmp);
+            reports.push(Report {
+                size,
+                implementation,
+                times,
+                bytes: (size.m * size.k + size.k * size.n + size.m * size.n) * mem::size_of::<T>(),
+            });
+        }
+    }
+
+    match args.format {
+        ReportFormat::Table => print_table(&reports),
+        ReportFormat::Csv => print_csv(&reports),
+        ReportFormat::Json => println!("{}", to_json(&reports)),
+    }
+}
+
+/// Matrix with values that are uniformly distributed in `[-1, 1)`, or in `[-8, 8]` for integers
+pub fn random<T: Element>(generator: &mut Generator, rows: usize, cols: usize) -> Matrix<T> {
+    let distribution = match T::TYPE {
+        ElementType::I32 | ElementType::I64 => Distribution::Integers { low: -8, high: 8 },
+        _ => Distribution::Uniform {
+            low: -1.0,
+            high: 1.0,
+        },
+    };
+
+    generator.matrix(rows, cols, distribution)
+}
+
+const COLUMNS: [&str; 8] = [
+    "size",
+    "implementation",
+    "min_ms",
+    "median_ms",
+    "p95_ms",
+    "stddev_ms",
+    "gflops",
+    "bandwidth_gbs",
+];
+
+/// Values of the report in the order of [COLUMNS]
+fn values(report: &Report) -> [String; 8] {
+    [
+        report.size.to_string(),
+        report.implementation.to_string(),
+        format!("{:.3}", report.min()),
+        format!("{:.3}", report.median()),
+        format!("{:.3}", report.p95()),
+        format!("{:.3}", report.stddev()),
+        format!("{:.2}", report.gflops()),
+        format!("{:.2}", report.bandwidth()),
+    ]
+}
+
+fn print_table(reports: &[Report]) {
+    let rows = reports.iter().map(values).collect::<Vec<_>>();
+    let widths = COLUMNS.map(str::len).map(|width| width.max(9));
+    let widths = rows.iter().fold(widths, |mut widths, row| {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.len());
+        }
+        widths
+    });
+
+    let line = |values: &[&str]| {
+        let cells = values
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(index, (value, width))| {
+                // names are aligned to the left, numbers to the right
+                if index < 2 {
+                    format!("{value:<width$}")
+                } else {
+                    format!("{value:>width$}")
+                }
+            })
+            .collect::<Vec<_>>();
+        println!("{}", cells.join("  ").trim_end());
+    };
+
+    line(&COLUMNS);
+    for row in &rows {
+        line(&row.each_ref().map(String::as_str));
+    }
+}
+
+fn print_csv(reports: &[Report]) {
+    println!("{}", COLUMNS.join(","));
+    for report in reports {
+        println!("{}", values(report).join(","));
+    }
+}
+
+fn to_json(reports: &[Report]) -> String {
+    let reports = reports
+        .iter()
+        .map(|report| {
+            let Size { m, k, n } = report.size;
+            let fields = [
+                ("m", m.to_string()),
+                ("k", k.to_string()),
+                ("n", n.to_string()),
+                ("implementation", string(&report.implementation.to_string())),
+                ("repetitions", report.times.len().to_string()),
+                ("min_ms", number(report.min())),
+                ("median_ms", number(report.median())),
+                ("p95_ms", number(report.p95())),
+                ("stddev_ms", number(report.stddev())),
+                ("gflops", number(report.gflops())),
+                ("bandwidth_gbs", number(report.bandwidth())),
+            ];
+            object(&fields, 1)
+        })
+        .collect::<Vec<_>>();
+
+    array(&reports, 0)
+}
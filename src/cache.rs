@@ -0,0 +1,192 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use opencl3::context::Context;
+use opencl3::device::Device;
+use opencl3::program::Program;
+
+use super::MatmulError;
+use super::Result;
+
+/// Extension of the cached program binaries
+const EXTENSION: &str = "bin";
+
+/// On disk cache of compiled OpenCl programs
+///
+/// Programs are keyed by the device name, driver version, program source and build options, so a
+/// driver update or a kernel change never picks up a stale binary
+#[derive(Clone, Debug)]
+pub struct ProgramCache {
+    /// Directory with the binaries, `None` if caching is disabled
+    dir: Option<PathBuf>,
+}
+
+impl Default for ProgramCache {
+    /// Cache in `$MATMUL_CACHE_DIR`, `$XDG_CACHE_HOME/matmul` or `$HOME/.cache/matmul`, whichever
+    /// is set first
+    ///
+    /// Caching is disabled if none of them are set
+    fn default() -> Self {
+        Self { dir: default_dir() }
+    }
+}
+
+/// `$MATMUL_CACHE_DIR`, `$XDG_CACHE_HOME/matmul` or `$HOME/.cache/matmul`, whichever is set first
+pub(crate) fn default_dir() -> Option<PathBuf> {
+    env::var_os("MATMUL_CACHE_DIR")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("XDG_CACHE_HOME").map(|dir| Path::new(&dir).join("matmul")))
+        .or_else(|| env::var_os("HOME").map(|dir| Path::new(&dir).join(".cache/matmul")))
+}
+
+impl ProgramCache {
+    /// Cache in the provided directory, it is created on first use
+    pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+        }
+    }
+
+    /// Cache that never stores anything, every program is built from source
+    pub fn disabled() -> Self {
+        Self { dir: None }
+    }
+
+    /// Directory with the binaries, `None` if caching is disabled
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Removes every cached binary
+    pub fn clear(&self) -> Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds `source` for `device`, loading the binary from the cache when possible
+    pub(crate) fn build(
+        &self,
+        context: &Context,
+        device: &Device,
+        source: &str,
+        options: &str,
+    ) -> Result<Program> {
+        let path = match &self.dir {
+            Some(dir) => {
+                let key = key(device, source, options)?;
+                Some(dir.join(format!("{key:016x}.{EXTENSION}")))
+            }
+            None => None,
+        };
+
+        // a binary that fails to load is treated as a cache miss, it is overwritten below
+        if let Some(binary) = path.as_ref().and_then(|path| fs::read(path).ok()) {
+            let program =
+                unsafe { Program::create_from_binary(context, &[device.id()], &[&binary]) };
+            if let Ok(mut program) = program {
+                if program.build(context.devices(), options).is_ok() {
+                    return Ok(program);
+                }
+            }
+        }
+
+        let program = Program::create_and_build_from_source(context, source, options)
+            .map_err(|log| MatmulError::KernelBuild { log })?;
+
+        if let Some(path) = path {
+            // failing to cache a program should never fail the multiplication
+            let _ = store(&path, &program);
+        }
+
+        Ok(program)
+    }
+}
+
+/// Key of a program in the cache
+fn key(device: &Device, source: &str, options: &str) -> Result<u64> {
+    let device_name = device.name()?;
+    let driver_version = device.driver_version()?;
+
+    let mut hash = Fnv::default();
+    for part in [&device_name, &driver_version, source, options] {
+        hash.write(part.as_bytes());
+        // separator, so that moving a suffix from one part to the next changes the key
+        hash.write(&[0]);
+    }
+
+    Ok(hash.0)
+}
+
+/// Writes the binary of `program` to `path`, see [write_atomically]
+fn store(path: &Path, program: &Program) -> Result<()> {
+    let binaries = program.get_binaries()?;
+    let Some(binary) = binaries.first() else {
+        return Ok(());
+    };
+
+    write_atomically(path, binary)?;
+
+    Ok(())
+}
+
+/// Writes `contents` to `path`, creating its directory if needed
+///
+/// The contents are written to a temporary file first, named after the process and a counter so
+/// that no other thread or process writes to it, and then renamed. Concurrent readers never see a
+/// partially written file
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let tmp = path.with_extension(format!("{}.{count}.tmp", std::process::id()));
+    fs::write(&tmp, contents)?;
+
+    if let Err(e) = fs::rename(&tmp, path) {
+        // best effort, the rename error is the one worth reporting
+        let _ = fs::remove_file(tmp);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// 64 bit FNV-1a, unlike `DefaultHasher` it is stable between Rust versions
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use clap::ValueEnum;
+
+use matmul::{DeviceInfo, DeviceType, PlatformInfo};
+
+use crate::json::{array, object, string};
+
+/// Prints every OpenCl platform and device, as JSON if `json` is set
+pub fn print(json: bool) {
+    let platforms = match matmul::platforms() {
+        Ok(res) => res,
+        Err(e) => {
+            eprintln!("unable to list devices: {}", e);
+            return;
+        }
+    };
+
+    if json {
+        println!("{}", to_json(&platforms));
+        return;
+    }
+
+    if platforms.is_empty() {
+        println!("no OpenCl platforms found");
+    }
+
+    for (index, platform) in platforms.iter().enumerate() {
+        println!(
+            "Platform {}: {} ({}, {})",
+            index, platform.name, platform.vendor, platform.version
+        );
+
+        for device in &platform.devices {
+            let indices = device
+                .indices
+                .iter()
+                .map(|(device_type, index)| format!("{} {}", name(*device_type), index))
+                .collect::<Vec<_>>();
+
+            println!("  {}", device.name);
+            println!("    Type:                {}", device.kind);
+            println!("    Indices:             {}", indices.join(", "));
+            println!("    Unified memory:      {}", yes_no(device.unified_memory));
+            println!("    Compute units:       {}", device.compute_units);
+            println!("    Max work group size: {}", device.max_work_group_size);
+            println!("    Local memory:        {}", size(device.local_memory));
+            println!("    Global memory:       {}", size(device.global_memory));
+            println!("    fp64:                {}", yes_no(device.fp64));
+            println!("    fp16:                {}", yes_no(device.fp16));
+        }
+    }
+}
+
+fn to_json(platforms: &[PlatformInfo]) -> String {
+    let platforms = platforms
+        .iter()
+        .map(|platform| {
+            let devices = platform
+                .devices
+                .iter()
+                .map(device_to_json)
+                .collect::<Vec<_>>();
+
+            let fields = [
+                ("name", string(&platform.name)),
+                ("vendor", string(&platform.vendor)),
+                ("version", string(&platform.version)),
+                ("devices", array(&devices, 2)),
+            ];
+            object(&fields, 1)
+        })
+        .collect::<Vec<_>>();
+
+    array(&platforms, 0)
+}
+
+fn device_to_json(device: &DeviceInfo) -> String {
+    let indices = device
+        .indices
+        .iter()
+        .map(|(device_type, index)| format!("\"{}\": {}", name(*device_type), index))
+        .collect::<Vec<_>>();
+
+    let fields = [
+        ("name", string(&device.name)),
+        ("type", string(device.kind)),
+        ("indices", format!("{{{}}}", indices.join(", "))),
+        ("unified_memory", device.unified_memory.to_string()),
+        ("compute_units", device.compute_units.to_string()),
+        (
+            "max_work_group_size",
+            device.max_work_group_size.to_string(),
+        ),
+        ("local_memory", device.local_memory.to_string()),
+        ("global_memory", device.global_memory.to_string()),
+        ("fp64", device.fp64.to_string()),
+        ("fp16", device.fp16.to_string()),
+    ];
+    object(&fields, 3)
+}
+
+/// Name of `device_type` as it is passed on the command line
+fn name(device_type: DeviceType) -> String {
+    // unwrap is safe because no variant is skipped
+    device_type
+        .to_possible_value()
+        .unwrap()
+        .get_name()
+        .to_string()
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// Human readable size of `bytes`
+fn size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,353 @@
+use std::fmt::{Debug, Display};
+use std::ops::{Add, AddAssign, Mul, Sub};
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use half::{bf16, f16};
+
+/// Anything that can be stored in a [Matrix](crate::Matrix) and multiplied
+///
+/// Every element type has a matching OpenCl C type, so that the same kernels can be compiled for
+/// each of them
+///
+/// # Safety
+///
+/// Elements are copied to and from OpenCl devices byte by byte, so implementors must be plain
+/// numbers: no padding, no pointers and every bit pattern must be a valid value
+pub unsafe trait Element:
+    Copy
+    + Default
+    + PartialEq
+    + PartialOrd
+    + Debug
+    + Display
+    + FromStr<Err: Display>
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + AddAssign
+    + Send
+    + Sync
+    + 'static
+{
+    /// Type that the products are summed up in, `Self` for everything but half precision floats
+    type Acc: Element;
+
+    /// Name of the type, same as the one used on the command line
+    const NAME: &'static str;
+    /// Tag of the type, as stored in binary files
+    const TYPE: ElementType;
+    /// Name of the type in OpenCl C, this is how the elements are stored in device memory
+    const CL_TYPE: &'static str;
+    /// OpenCl extension the device must support to use this type, if any
+    const CL_EXTENSION: Option<&'static str> = None;
+    /// OpenCl C expression that loads element `i` of pointer `p` as `ACC`
+    const CL_LOAD: &'static str = "((p)[i])";
+    /// OpenCl C statement that stores `v` of type `ACC` as element `i` of pointer `p`
+    const CL_STORE: &'static str = "((p)[i] = (v))";
+    /// Difference between 1 and the next larger value, zero for integers
+    const EPSILON: f64;
+
+    /// Lossy conversion to `f64`, used for comparisons
+    fn to_f64(self) -> f64;
+    /// Lossy conversion from `f64`, integers saturate
+    fn from_f64(value: f64) -> Self;
+    /// Widen to the accumulator type
+    fn to_acc(self) -> Self::Acc;
+    /// Narrow an accumulated value back to `Self`
+    fn from_acc(acc: Self::Acc) -> Self;
+    /// Number of representable values between `self` and `other`, the absolute difference for
+    /// integers and [u64::MAX] if either is NaN
+    fn ulps(self, other: Self) -> u64;
+
+    /// `self + other`, integers wrap around on overflow like they do in the OpenCl kernels
+    #[inline]
+    fn wrapping_add(self, other: Self) -> Self {
+        self + other
+    }
+
+    /// `self - other`, integers wrap around on overflow like they do in the OpenCl kernels
+    #[inline]
+    fn wrapping_sub(self, other: Self) -> Self {
+        self - other
+    }
+
+    /// `self * other`, integers wrap around on overflow like they do in the OpenCl kernels
+    #[inline]
+    fn wrapping_mul(self, other: Self) -> Self {
+        self * other
+    }
+}
+
+/// [Element::ulps] of two floats given their bits and the number of bits of the type
+///
+/// The sign and magnitude bits are mapped to integers that are ordered like the floats, so that
+/// `-0.0` and `0.0` are the same and the smallest values of both signs are two ulps apart
+fn float_ulps(a: u64, b: u64, bits: u32, nan: bool) -> u64 {
+    if nan {
+        return u64::MAX;
+    }
+
+    let sign = 1 << (bits - 1);
+    let ordered = |value: u64| {
+        if value & sign == 0 {
+            value as i128
+        } else {
+            -((value & !sign) as i128)
+        }
+    };
+
+    (ordered(a) - ordered(b)).unsigned_abs() as u64
+}
+
+/// Addition, subtraction and multiplication of an element type, as passed to `impl_element`
+type Ops<T> = (fn(T, T) -> T, fn(T, T) -> T, fn(T, T) -> T);
+
+macro_rules! impl_element {
+    ($ty: ty, $name: expr, $tag: expr, $cl_type: expr, $eps: expr, $ulps: expr, $ops: expr) => {
+        impl_element!($ty, $name, $tag, $cl_type, $eps, $ulps, $ops, None);
+    };
+    (
+        $ty: ty,
+        $name: expr,
+        $tag: expr,
+        $cl_type: expr,
+        $eps: expr,
+        $ulps: expr,
+        $ops: expr,
+        $ext: expr
+    ) => {
+        unsafe impl Element for $ty {
+            type Acc = $ty;
+
+            const NAME: &'static str = $name;
+            const TYPE: ElementType = $tag;
+            const CL_TYPE: &'static str = $cl_type;
+            const CL_EXTENSION: Option<&'static str> = $ext;
+            const EPSILON: f64 = $eps;
+
+            #[inline]
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            #[inline]
+            fn from_f64(value: f64) -> Self {
+                value as $ty
+            }
+
+            #[inline]
+            fn to_acc(self) -> Self::Acc {
+                self
+            }
+
+            #[inline]
+            fn from_acc(acc: Self::Acc) -> Self {
+                acc
+            }
+
+            #[inline]
+            fn ulps(self, other: Self) -> u64 {
+                let ulps: fn($ty, $ty) -> u64 = $ulps;
+                ulps(self, other)
+            }
+
+            #[inline]
+            fn wrapping_add(self, other: Self) -> Self {
+                let (add, _, _): Ops<$ty> = $ops;
+                add(self, other)
+            }
+
+            #[inline]
+            fn wrapping_sub(self, other: Self) -> Self {
+                let (_, sub, _): Ops<$ty> = $ops;
+                sub(self, other)
+            }
+
+            #[inline]
+            fn wrapping_mul(self, other: Self) -> Self {
+                let (_, _, mul): Ops<$ty> = $ops;
+                mul(self, other)
+            }
+        }
+    };
+}
+
+impl_element!(
+    f32,
+    "f32",
+    ElementType::F32,
+    "float",
+    f32::EPSILON as f64,
+    |a, b| {
+        float_ulps(
+            a.to_bits().into(),
+            b.to_bits().into(),
+            32,
+            a.is_nan() || b.is_nan(),
+        )
+    },
+    (f32::add, f32::sub, f32::mul)
+);
+impl_element!(
+    f64,
+    "f64",
+    ElementType::F64,
+    "double",
+    f64::EPSILON,
+    |a, b| float_ulps(a.to_bits(), b.to_bits(), 64, a.is_nan() || b.is_nan()),
+    (f64::add, f64::sub, f64::mul),
+    Some("cl_khr_fp64")
+);
+impl_element!(
+    i32,
+    "i32",
+    ElementType::I32,
+    "int",
+    0.0,
+    |a, b| (a as i128 - b as i128).unsigned_abs() as u64,
+    (i32::wrapping_add, i32::wrapping_sub, i32::wrapping_mul)
+);
+impl_element!(
+    i64,
+    "i64",
+    ElementType::I64,
+    "long",
+    0.0,
+    |a, b| (a as i128 - b as i128).unsigned_abs() as u64,
+    (i64::wrapping_add, i64::wrapping_sub, i64::wrapping_mul)
+);
+
+unsafe impl Element for f16 {
+    type Acc = f32;
+
+    const NAME: &'static str = "f16";
+    const TYPE: ElementType = ElementType::F16;
+    const CL_TYPE: &'static str = "half";
+    // `vload_half` and `vstore_half` are core OpenCl, no need for `cl_khr_fp16`
+    const CL_LOAD: &'static str = "vload_half(i, p)";
+    const CL_STORE: &'static str = "vstore_half(v, i, p)";
+    const EPSILON: f64 = f16::EPSILON.to_f64_const();
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self.to_f64()
+    }
+
+    #[inline]
+    fn from_f64(value: f64) -> Self {
+        f16::from_f64(value)
+    }
+
+    #[inline]
+    fn to_acc(self) -> Self::Acc {
+        self.to_f32()
+    }
+
+    #[inline]
+    fn from_acc(acc: Self::Acc) -> Self {
+        f16::from_f32(acc)
+    }
+
+    #[inline]
+    fn ulps(self, other: Self) -> u64 {
+        float_ulps(
+            self.to_bits().into(),
+            other.to_bits().into(),
+            16,
+            self.is_nan() || other.is_nan(),
+        )
+    }
+}
+
+unsafe impl Element for bf16 {
+    type Acc = f32;
+
+    const NAME: &'static str = "bf16";
+    const TYPE: ElementType = ElementType::Bf16;
+    // OpenCl C has no bfloat16, the bits are stored as is and expanded to the upper half of a float
+    const CL_TYPE: &'static str = "ushort";
+    const CL_LOAD: &'static str = "as_float((uint)(p)[i] << 16)";
+    // rounds to nearest even
+    const CL_STORE: &'static str =
+        "((p)[i] = (ushort)((as_uint(v) + 0x7FFF + ((as_uint(v) >> 16) & 1)) >> 16))";
+    const EPSILON: f64 = bf16::EPSILON.to_f64_const();
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self.to_f64()
+    }
+
+    #[inline]
+    fn from_f64(value: f64) -> Self {
+        bf16::from_f64(value)
+    }
+
+    #[inline]
+    fn to_acc(self) -> Self::Acc {
+        self.to_f32()
+    }
+
+    #[inline]
+    fn from_acc(acc: Self::Acc) -> Self {
+        bf16::from_f32(acc)
+    }
+
+    #[inline]
+    fn ulps(self, other: Self) -> u64 {
+        float_ulps(
+            self.to_bits().into(),
+            other.to_bits().into(),
+            16,
+            self.is_nan() || other.is_nan(),
+        )
+    }
+}
+
+/// Element types that are supported on the command line
+///
+/// Discriminants are the tags of the types in binary files, so they must never change
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum ElementType {
+    /// 32 bit float
+    #[default]
+    F32 = 0,
+    /// 64 bit float, requires `cl_khr_fp64` on OpenCl devices
+    F64 = 1,
+    /// 32 bit signed integer
+    I32 = 2,
+    /// 64 bit signed integer
+    I64 = 3,
+    /// 16 bit IEEE float, multiplied with 32 bit accumulation
+    F16 = 4,
+    /// 16 bit brain float, multiplied with 32 bit accumulation
+    Bf16 = 5,
+}
+
+impl ElementType {
+    /// Every supported type
+    pub const ALL: [Self; 6] = [
+        Self::F32,
+        Self::F64,
+        Self::I32,
+        Self::I64,
+        Self::F16,
+        Self::Bf16,
+    ];
+
+    /// Type with the tag `tag`, see [Element::TYPE]
+    pub fn from_tag(tag: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|ty| *ty as u16 == tag)
+    }
+}
+
+pub(crate) fn as_bytes<T: Element>(data: &[T]) -> &[u8] {
+    // safe because elements are plain numbers, see [Element]
+    unsafe { std::slice::from_raw_parts(data.as_ptr().cast(), std::mem::size_of_val(data)) }
+}
+
+pub(crate) fn as_bytes_mut<T: Element>(data: &mut [T]) -> &mut [u8] {
+    // safe because elements are plain numbers and every bit pattern is a valid one
+    unsafe { std::slice::from_raw_parts_mut(data.as_mut_ptr().cast(), std::mem::size_of_val(data)) }
+}
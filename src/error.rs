@@ -0,0 +1,175 @@
+use std::fmt::{self, Display};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use opencl3::error_codes::ClError;
+
+use super::mode::DeviceSelector;
+use super::sources::KernelConfig;
+use super::Isa;
+
+/// Everything that can go wrong while reading, writing or multiplying matrices
+#[derive(Debug)]
+pub enum MatmulError {
+    /// Matrices can not be multiplied, columns of `left` are not equal to the rows of `right`
+    DimensionMismatch {
+        /// `(rows, cols)` of the left operand
+        left: (usize, usize),
+        /// `(rows, cols)` of the right operand
+        right: (usize, usize),
+    },
+    /// Output matrix does not have the shape of the product
+    OutputMismatch {
+        /// `(rows, cols)` of the product
+        expected: (usize, usize),
+        /// `(rows, cols)` of the output matrix
+        actual: (usize, usize),
+    },
+    /// Batches of matrices can not be multiplied pairwise
+    InvalidBatch(String),
+    /// Amount of provided data does not match `rows * cols`
+    DataSize {
+        rows: usize,
+        cols: usize,
+        /// Actual amount of elements provided
+        len: usize,
+    },
+    /// Input could not be parsed
+    Parse {
+        /// Line of the input where the error is, starts at 1
+        line: usize,
+        /// Column of the input where the error is, starts at 1
+        column: usize,
+        message: String,
+    },
+    /// File is not valid in the format it claims to be in
+    Format {
+        /// Name of the format
+        format: &'static str,
+        message: String,
+    },
+    /// Dimension, stride or buffer length does not fit the 32 bit indices of the OpenCl kernels
+    TooLarge {
+        /// What is too large, a kernel argument or the length of a buffer
+        name: &'static str,
+        value: usize,
+    },
+    /// Kernel config is not valid, or needs more than the device offers
+    InvalidConfig {
+        config: KernelConfig,
+        /// Name of the device
+        device: String,
+    },
+    /// No OpenCl device matches the selector
+    NoDevice(DeviceSelector),
+    /// The host can not run the microkernel for the instruction set
+    UnsupportedIsa(Isa),
+    /// Device can not work with the requested element type
+    UnsupportedType {
+        /// Name of the element type, as in [Element::NAME](crate::Element::NAME)
+        name: &'static str,
+        /// Name of the device
+        device: String,
+    },
+    /// OpenCl program failed to build
+    KernelBuild {
+        /// Build log of the program as reported by the OpenCl compiler
+        log: String,
+    },
+    /// Any other error returned by the OpenCl runtime
+    OpenCl(ClError),
+    /// Failed to read or write a file
+    Io(io::Error),
+    /// Error in a specific file
+    File {
+        path: PathBuf,
+        /// What went wrong in the file
+        source: Box<MatmulError>,
+    },
+}
+
+impl MatmulError {
+    /// Attaches the path of the file the error happened in
+    pub(crate) fn in_file(self, path: &Path) -> Self {
+        Self::File {
+            path: path.to_path_buf(),
+            source: Box::new(self),
+        }
+    }
+}
+
+impl Display for MatmulError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DimensionMismatch { left, right } => write!(
+                f,
+                "dimension mismatch, can not multiply {}x{} by {}x{}",
+                left.0, left.1, right.0, right.1
+            ),
+            Self::OutputMismatch { expected, actual } => write!(
+                f,
+                "output mismatch, expected a {}x{} matrix, got {}x{}",
+                expected.0, expected.1, actual.0, actual.1
+            ),
+            Self::InvalidBatch(msg) => write!(f, "invalid batch, {msg}"),
+            Self::DataSize { rows, cols, len } => {
+                write!(f, "invalid data, {rows} * {cols} != {len} (data size)")
+            }
+            Self::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "parse error at {line}:{column}, {message}"),
+            Self::Format { format, message } => write!(f, "invalid {format} file, {message}"),
+            Self::TooLarge { name, value } => {
+                write!(f, "{name} is {value}, more than OpenCl kernels can index")
+            }
+            Self::InvalidConfig { config, device } => {
+                write!(f, "device `{device}` can not run kernel config {config:?}")
+            }
+            Self::NoDevice(selector) => write!(f, "no OpenCl device matches {selector}"),
+            Self::UnsupportedIsa(isa) => write!(f, "host does not support instruction set {isa}"),
+            Self::UnsupportedType { name, device } => {
+                write!(f, "device `{device}` does not support element type {name}")
+            }
+            Self::KernelBuild { log } => write!(f, "unable to build kernel:\n{log}"),
+            Self::OpenCl(e) => write!(f, "OpenCl error: {e}"),
+            Self::Io(e) => write!(f, "{e}"),
+            Self::File { path, source } => match source.as_ref() {
+                Self::Parse {
+                    line,
+                    column,
+                    message,
+                } => write!(
+                    f,
+                    "parse error at {}:{line}:{column}, {message}",
+                    path.display()
+                ),
+                source => write!(f, "{}: {source}", path.display()),
+            },
+        }
+    }
+}
+
+impl std::error::Error for MatmulError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::OpenCl(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::File { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<ClError> for MatmulError {
+    fn from(value: ClError) -> Self {
+        Self::OpenCl(value)
+    }
+}
+
+impl From<io::Error> for MatmulError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+
+use matmul::{Distribution, Element, FileFormat, Generator, Result, SparseMatrix};
+
+/// Options of the generate command
+#[derive(Debug, clap::Args)]
+pub struct GenerateArgs {
+    /// File the matrices are written to, in the format given with `--output-format`
+    pub output: String,
+    /// `ROWSxCOLS` for a single matrix, or `MxKxN` for an input file with an `M x K` and a `K x N`
+    /// matrix
+    #[arg(short, long)]
+    pub shape: Shape,
+    /// Distribution of the values
+    #[arg(long, value_enum, default_value_t)]
+    pub distribution: DistributionKind,
+    /// Lower bound of uniform values and of integers
+    #[arg(long, default_value_t = -1.0, allow_negative_numbers = true)]
+    pub low: f64,
+    /// Upper bound of uniform values and of integers
+    #[arg(long, default_value_t = 1.0, allow_negative_numbers = true)]
+    pub high: f64,
+    /// Mean of normal values
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub mean: f64,
+    /// Standard deviation of normal values
+    #[arg(long, default_value_t = 1.0)]
+    pub std_dev: f64,
+    /// Number of nonzero diagonals on each side of the main one of banded matrices
+    #[arg(long, default_value_t = 1)]
+    pub bandwidth: usize,
+    /// Fraction of nonzero values of sparse matrices
+    #[arg(long, default_value_t = 0.1)]
+    pub density: f64,
+    /// Condition number of ill-conditioned matrices
+    #[arg(long, default_value_t = 1e6)]
+    pub condition: f64,
+    /// Seed of the random values, a random one is used and printed by default
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+impl GenerateArgs {
+    /// Distribution with the parameters of the options, or why they are invalid
+    fn distribution(&self) -> std::result::Result<Distribution, String> {
+        let res = match self.distribution {
+            DistributionKind::Uniform => Distribution::Uniform {
+                low: self.low,
+                high: self.high,
+            },
+            DistributionKind::Normal => Distribution::Normal {
+                mean: self.mean,
+                std_dev: self.std_dev,
+            },
+            DistributionKind::Integers => {
+                let (low, high) = (self.low.ceil() as i64, self.high.floor() as i64);
+                if low > high {
+                    return Err(format!("no integers in [{}, {}]", self.low, self.high));
+                }
+                Distribution::Integers { low, high }
+            }
+            DistributionKind::Identity => Distribution::Identity,
+            DistributionKind::Diagonal => Distribution::Diagonal,
+            DistributionKind::Banded => Distribution::Banded {
+                bandwidth: self.bandwidth,
+            },
+            DistributionKind::Sparse => {
+                if !(0.0..=1.0).contains(&self.density) {
+                    return Err(format!("density {} is not in [0, 1]", self.density));
+                }
+                Distribution::Sparse {
+                    density: self.density,
+                }
+            }
+            DistributionKind::IllConditioned => {
+                if self.condition < 1.0 {
+                    return Err(format!("condition {} is less than 1", self.condition));
+                }
+                Distribution::IllConditioned {
+                    condition: self.condition,
+                }
+            }
+        };
+
+        Ok(res)
+    }
+}
+
+/// Matrices that are generated
+#[derive(Copy, Clone, Debug)]
+pub enum Shape {
+    /// A single `rows x cols` matrix
+    Matrix { rows: usize, cols: usize },
+    /// Both operands of an `m x k` by `k x n` product
+    Operands { m: usize, k: usize, n: usize },
+}
+
+impl FromStr for Shape {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let dims = s
+            .split('x')
+            .map(|dim| dim.trim().parse::<usize>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid shape `{s}`, {e}"))?;
+
+        match dims[..] {
+            [rows, cols] => Ok(Self::Matrix { rows, cols }),
+            [m, k, n] => Ok(Self::Operands { m, k, n }),
+            _ => Err(format!(
+                "invalid shape `{s}`, expected `ROWSxCOLS` or `MxKxN`"
+            )),
+        }
+    }
+}
+
+impl Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Matrix { rows, cols } => write!(f, "{rows}x{cols}"),
+            Self::Operands { m, k, n } => write!(f, "{m}x{k}x{n}"),
+        }
+    }
+}
+
+/// Kinds of [Distribution], their parameters are separate options
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum DistributionKind {
+    /// Uniform in `[--low, --high)`
+    #[default]
+    Uniform,
+    /// Normal with `--mean` and `--std-dev`
+    Normal,
+    /// Uniform integers in `[--low, --high]`
+    Integers,
+    Identity,
+    /// Uniform in `[-1, 1)` on the diagonal
+    Diagonal,
+    /// Uniform in `[-1, 1)` at most `--bandwidth` away from the diagonal
+    Banded,
+    /// Uniform in `[-1, 1)` with probability `--density`
+    Sparse,
+    /// Condition number `--condition`
+    #[value(name = "ill-conditioned")]
+    IllConditioned,
+}
+
+/// Writes random matrices of `T` to the output file in `format`
+pub fn run<T: Element>(args: &GenerateArgs, format: FileFormat) {
+    let distribution = match args.distribution() {
+        Ok(res) => res,
+        Err(e) => {
+            eprintln!("unable to generate matrices, {}", e);
+            return;
+        }
+    };
+
+    let seed = args.seed.unwrap_or_else(|| {
+        let seed = rand::random();
+        println!("Seed: {}", seed);
+        seed
+    });
+
+    let mut generator = Generator::new(seed);
+    let path = Path::new(&args.output);
+
+    let res = match args.shape {
+        Shape::Matrix { rows, cols } => {
+            let matrix = generator.matrix::<T>(rows, cols, distribution);
+            if args.distribution == DistributionKind::Sparse && format == FileFormat::Mtx {
+                write_sparse(path, &SparseMatrix::from_dense(&matrix))
+            } else {
+                matmul::write_file_as(path, &matrix, format)
+            }
+        }
+        Shape::Operands { m, k, n } => {
+            let a = generator.matrix::<T>(m, k, distribution);
+            let b = generator.matrix::<T>(k, n, distribution);
+            matmul::write_operands(path, &a, &b, format)
+        }
+    };
+
+    if let Err(e) = res {
+        eprintln!("unable to write {} matrices, {}", args.shape, e);
+    }
+}
+
+/// Writes a sparse matrix in the Matrix Market coordinate layout, so only the nonzeroes are stored
+fn write_sparse<T: Element>(path: &Path, matrix: &SparseMatrix<T>) -> Result<()> {
+    let file = fs::File::create(path)?;
+    let mut writer = io::BufWriter::new(file);
+
+    matmul::write_mtx_sparse(&mut writer, matrix)?;
+    writer.flush()?;
+
+    Ok(())
+}
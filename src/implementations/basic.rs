@@ -1,7 +1,8 @@
-use std::io;
 use std::time;
 
+use crate::multiplier::{Gemm, Transpose};
 use crate::multiplier::{Multiplier, MultiplierInfo, MultiplierStat};
+use crate::Element;
 use crate::Matrix;
 use crate::Result;
 
@@ -10,22 +11,37 @@ pub struct BasicMultiplier {
     last_time: Option<u64>,
 }
 
-impl Multiplier for BasicMultiplier {
-    fn multiply(&mut self, m1: &Matrix, m2: &Matrix) -> Result<Matrix> {
+impl<T: Element> Multiplier<T> for BasicMultiplier {
+    fn gemm(
+        &mut self,
+        params: Gemm<T>,
+        a: &Matrix<T>,
+        b: &Matrix<T>,
+        c: &mut Matrix<T>,
+    ) -> Result<()> {
         let instant = time::Instant::now();
-        if m1.cols != m2.rows {
-            return Err(io::Error::from(io::ErrorKind::InvalidData).into());
-        }
+        super::check_gemm_dimensions(&params, a, b, c)?;
+
+        let inner = params.trans_a.shape(a).1;
 
-        let mut res = Matrix::create_empty(m1.rows, m2.cols);
+        let op_a = |i, k| match params.trans_a {
+            Transpose::No => a.get(i, k),
+            Transpose::Yes => a.get(k, i),
+        };
+        let op_b = |k, j| match params.trans_b {
+            Transpose::No => b.get(k, j),
+            Transpose::Yes => b.get(j, k),
+        };
 
-        for i in 0..m1.rows {
-            for j in 0..m2.cols {
-                let mut sum = 0f32;
-                for k in 0..m1.cols {
-                    sum += m1.get(i, k) * m2.get(k, j);
+        for i in 0..c.rows {
+            for j in 0..c.cols {
+                let mut sum = T::Acc::default();
+                for k in 0..inner {
+                    let product = op_a(i, k).to_acc().wrapping_mul(op_b(k, j).to_acc());
+                    sum = sum.wrapping_add(product);
                 }
-                res.set(i, j, sum);
+                let value = super::scale(&params, sum, c.get(i, j));
+                c.set(i, j, value);
             }
         }
 
@@ -33,7 +49,7 @@ impl Multiplier for BasicMultiplier {
 
         self.last_time = Some(elapsed);
 
-        Ok(res)
+        Ok(())
     }
 
     fn info(&self) -> Result<MultiplierInfo> {
@@ -44,6 +60,7 @@ impl Multiplier for BasicMultiplier {
         self.last_time.map(|total_time| MultiplierStat {
             total_time,
             gpu_time: 0,
+            devices: vec![],
         })
     }
 }
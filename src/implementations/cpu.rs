@@ -0,0 +1,177 @@
+use std::num::NonZeroUsize;
+use std::thread;
+use std::time;
+
+use crate::multiplier::{Gemm, Transpose};
+use crate::multiplier::{Multiplier, MultiplierInfo, MultiplierStat};
+use crate::Element;
+use crate::Matrix;
+use crate::Result;
+
+/// Depth of a block, `KC` rows of the packed right operand are used for every row of the result
+pub(super) const KC: usize = 128;
+/// Width of a block, `KC x NC` accumulators of the right operand should fit in the L2 cache
+pub(super) const NC: usize = 256;
+
+/// Multithreaded multiplication on the host
+///
+/// Operands are packed into row major buffers of accumulators, so transpositions and narrow
+/// element types cost nothing in the inner loop. Rows of the result are split into one panel per
+/// thread and every panel is computed block by block, so that a block of the right operand stays
+/// in cache while it is multiplied by every row of the panel
+pub struct CpuMultiplier {
+    threads: usize,
+    last_time: Option<u64>,
+}
+
+impl Default for CpuMultiplier {
+    /// One thread per available core
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl CpuMultiplier {
+    /// Multiplier that uses `threads` threads, or one per available core if `None`
+    pub fn new(threads: Option<NonZeroUsize>) -> Self {
+        let threads = threads
+            .or_else(|| thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get);
+
+        Self {
+            threads,
+            last_time: None,
+        }
+    }
+
+    /// Number of threads the multiplication is split between
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+}
+
+impl<T: Element> Multiplier<T> for CpuMultiplier {
+    fn gemm(
+        &mut self,
+        params: Gemm<T>,
+        a: &Matrix<T>,
+        b: &Matrix<T>,
+        c: &mut Matrix<T>,
+    ) -> Result<()> {
+        let instant = time::Instant::now();
+        super::check_gemm_dimensions(&params, a, b, c)?;
+
+        parallel_gemm(self.threads, &params, a, b, c, multiply_panel::<T>);
+
+        let elapsed = instant.elapsed().as_millis() as u64;
+
+        self.last_time = Some(elapsed);
+
+        Ok(())
+    }
+
+    fn info(&self) -> Result<MultiplierInfo> {
+        Ok(MultiplierInfo::OnDeviceMultiplier)
+    }
+
+    fn stat(&self) -> Option<MultiplierStat> {
+        self.last_time.map(|total_time| MultiplierStat {
+            total_time,
+            gpu_time: 0,
+            devices: vec![],
+        })
+    }
+}
+
+/// Computes `c = alpha * op(a) * op(b) + beta * c` with the rows of `c` split into one panel per
+/// thread
+///
+/// `panel(a, b, res, n, k)` adds the products of the rows of the packed left operand `a` and the
+/// packed `k x n` right operand `b` to `res`
+pub(super) fn parallel_gemm<T, F>(
+    threads: usize,
+    params: &Gemm<T>,
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+    c: &mut Matrix<T>,
+    panel: F,
+) where
+    T: Element,
+    F: Fn(&[T::Acc], &[T::Acc], &mut [T::Acc], usize, usize) + Sync,
+{
+    let (m, n) = (c.rows, c.cols);
+    let k = params.trans_a.shape(a).1;
+
+    if c.data.is_empty() {
+        return;
+    }
+
+    let packed_a = pack(a, params.trans_a);
+    let packed_b = pack(b, params.trans_b);
+
+    let rows_per_thread = m.div_ceil(threads);
+
+    thread::scope(|scope| {
+        let panels = c.data.chunks_mut(rows_per_thread * n);
+
+        for (index, panel_c) in panels.enumerate() {
+            let first_row = index * rows_per_thread;
+            let rows = panel_c.len() / n;
+            let panel_a = &packed_a[first_row * k..(first_row + rows) * k];
+            let packed_b = &packed_b;
+            let panel = &panel;
+
+            scope.spawn(move || {
+                let mut sums = vec![T::Acc::default(); panel_c.len()];
+                panel(panel_a, packed_b, &mut sums, n, k);
+
+                for (el, sum) in panel_c.iter_mut().zip(sums) {
+                    *el = super::scale(params, sum, *el);
+                }
+            });
+        }
+    });
+}
+
+/// Row major copy of `op(matrix)` widened to accumulators
+fn pack<T: Element>(matrix: &Matrix<T>, trans: Transpose) -> Vec<T::Acc> {
+    match trans {
+        Transpose::No => matrix.iter().map(|el| el.to_acc()).collect(),
+        Transpose::Yes => (0..matrix.cols)
+            .flat_map(|col| (0..matrix.rows).map(move |row| matrix.get(row, col).to_acc()))
+            .collect(),
+    }
+}
+
+/// Adds the products of the rows of the packed left operand and the packed `k x n` right operand to
+/// `res`
+pub(super) fn multiply_panel<T: Element>(
+    a: &[T::Acc],
+    b: &[T::Acc],
+    res: &mut [T::Acc],
+    n: usize,
+    k: usize,
+) {
+    let rows = res.len() / n;
+
+    for kb in (0..k).step_by(KC) {
+        let kc = KC.min(k - kb);
+
+        for nb in (0..n).step_by(NC) {
+            let nc = NC.min(n - nb);
+
+            for i in 0..rows {
+                let row_a = &a[i * k + kb..][..kc];
+                let row_res = &mut res[i * n + nb..][..nc];
+
+                // the innermost loop runs over contiguous memory, so it is easy to vectorize
+                for (p, &el_a) in row_a.iter().enumerate() {
+                    let row_b = &b[(kb + p) * n + nb..][..nc];
+                    for (el, &el_b) in row_res.iter_mut().zip(row_b) {
+                        *el = el.wrapping_add(el_a.wrapping_mul(el_b));
+                    }
+                }
+            }
+        }
+    }
+}
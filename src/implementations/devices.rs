@@ -0,0 +1,218 @@
+use clap::ValueEnum;
+use opencl3::device::get_all_devices;
+use opencl3::device::Device;
+use opencl3::device::{CL_DEVICE_TYPE_ACCELERATOR, CL_DEVICE_TYPE_ALL};
+use opencl3::device::{CL_DEVICE_TYPE_CPU, CL_DEVICE_TYPE_GPU};
+use opencl3::error_codes::{ClError, CL_DEVICE_NOT_FOUND, CL_PLATFORM_NOT_FOUND_KHR};
+use opencl3::platform::get_platforms;
+
+use crate::mode::{DeviceMatch, DeviceSelector, DeviceType};
+use crate::MatmulError;
+use crate::Result;
+
+/// OpenCl platform and its devices, see [platforms]
+#[derive(Clone, Debug)]
+pub struct PlatformInfo {
+    pub name: String,
+    pub vendor: String,
+    /// OpenCl version supported by the platform, as reported by it
+    pub version: String,
+    pub devices: Vec<DeviceInfo>,
+}
+
+/// Properties of an OpenCl device that matter for multiplication, see [platforms]
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    pub name: String,
+    /// OpenCl type of the device: `gpu`, `cpu`, `accelerator` or `custom`
+    pub kind: &'static str,
+    /// Index of the device under every [DeviceType] that includes it, these are the indices that
+    /// the OpenCl modes expect
+    pub indices: Vec<(DeviceType, usize)>,
+    /// Whether the device shares memory with the host, which is what tells integrated gpus apart
+    pub unified_memory: bool,
+    pub compute_units: u32,
+    pub max_work_group_size: usize,
+    /// Size of the local memory in bytes
+    pub local_memory: u64,
+    /// Size of the global memory in bytes
+    pub global_memory: u64,
+    /// Whether the device supports `cl_khr_fp64`
+    pub fp64: bool,
+    /// Whether the device supports `cl_khr_fp16`
+    pub fp16: bool,
+}
+
+impl DeviceInfo {
+    fn new(device: Device, indices: Vec<(DeviceType, usize)>) -> Result<Self> {
+        let dev_type = device.dev_type()?;
+        let kind = [
+            (CL_DEVICE_TYPE_GPU, "gpu"),
+            (CL_DEVICE_TYPE_CPU, "cpu"),
+            (CL_DEVICE_TYPE_ACCELERATOR, "accelerator"),
+        ]
+        .into_iter()
+        .find(|(ty, _)| dev_type & ty != 0)
+        .map_or("custom", |(_, kind)| kind);
+
+        let extensions = device.extensions()?;
+        let has_extension = |extension| extensions.split(' ').any(|ext| ext == extension);
+
+        Ok(Self {
+            name: device.name()?,
+            kind,
+            indices,
+            unified_memory: device.host_unified_memory()?,
+            compute_units: device.max_compute_units()?,
+            max_work_group_size: device.max_work_group_size()?,
+            local_memory: device.local_mem_size()?,
+            global_memory: device.global_mem_size()?,
+            fp64: has_extension("cl_khr_fp64"),
+            fp16: has_extension("cl_khr_fp16"),
+        })
+    }
+}
+
+/// Every OpenCl platform with its devices
+///
+/// Devices carry their index under every [DeviceType], so they can be picked by the OpenCl modes
+pub fn platforms() -> Result<Vec<PlatformInfo>> {
+    let platforms = match get_platforms() {
+        Ok(platforms) => platforms,
+        Err(ClError(CL_PLATFORM_NOT_FOUND_KHR)) => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    let filtered = DeviceType::value_variants()
+        .iter()
+        .map(|&device_type| Ok((device_type, filter(device_type)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut res = vec![];
+
+    for platform in platforms {
+        let ids = match platform.get_devices(CL_DEVICE_TYPE_ALL) {
+            Ok(ids) => ids,
+            Err(ClError(CL_DEVICE_NOT_FOUND)) => vec![],
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut devices = vec![];
+        for id in ids {
+            let device = Device::from(id);
+            let indices = filtered
+                .iter()
+                .filter_map(|(device_type, devices)| {
+                    let index = devices.iter().position(|other| other.id() == device.id())?;
+                    Some((*device_type, index))
+                })
+                .collect();
+
+            devices.push(DeviceInfo::new(device, indices)?);
+        }
+
+        res.push(PlatformInfo {
+            name: platform.name()?,
+            vendor: platform.vendor()?,
+            version: platform.version()?,
+            devices,
+        });
+    }
+
+    Ok(res)
+}
+
+/// Device picked by `selector`
+///
+/// Devices are narrowed down by type first, in the order of [filter], then by platform and by
+/// name, vendor or position. `index` picks one of the remaining ones
+pub(super) fn select(selector: &DeviceSelector) -> Result<Device> {
+    matching(selector)?
+        .get(selector.index.unwrap_or_default())
+        .copied()
+        .ok_or_else(|| MatmulError::NoDevice(selector.clone()))
+}
+
+/// Every device picked by `selector`, or only the one at `index` if it is given
+pub(super) fn select_all(selector: &DeviceSelector) -> Result<Vec<Device>> {
+    match selector.index {
+        Some(_) => Ok(vec![select(selector)?]),
+        None => Ok(matching(selector)?),
+    }
+}
+
+/// Devices that match every criterion of `selector` but the index
+fn matching(selector: &DeviceSelector) -> Result<Vec<Device>> {
+    let mut devices = filter(selector.device_type.unwrap_or_default())?;
+
+    if let Some(regex) = &selector.platform {
+        let mut platform_ids = vec![];
+        for platform in get_platforms()? {
+            if regex.is_match(&platform.name()?) {
+                platform_ids.push(platform.id());
+            }
+        }
+
+        let mut res = vec![];
+        for device in devices {
+            if platform_ids.contains(&device.platform()?) {
+                res.push(device);
+            }
+        }
+        devices = res;
+    }
+
+    match &selector.device {
+        Some(DeviceMatch::Name(regex)) => {
+            let mut res = vec![];
+            for device in devices {
+                if regex.is_match(&device.name()?) || regex.is_match(&device.vendor()?) {
+                    res.push(device);
+                }
+            }
+            devices = res;
+        }
+        Some(DeviceMatch::Position { platform, device }) => {
+            let id = match get_platforms()?.get(*platform) {
+                Some(platform) => platform
+                    .get_devices(CL_DEVICE_TYPE_ALL)?
+                    .get(*device)
+                    .copied(),
+                None => None,
+            };
+            devices.retain(|device| Some(device.id()) == id);
+        }
+        None => (),
+    }
+
+    Ok(devices)
+}
+
+/// Devices of type `device_type` in the order in which they are indexed
+///
+/// Gpus are told apart by whether they share memory with the host, the ones that do are selected
+/// by [DeviceType::DGpu] and the others by [DeviceType::IGpu]
+pub(super) fn filter(device_type: DeviceType) -> Result<Vec<Device>> {
+    let ids = match get_all_devices(device_type.into()) {
+        Ok(ids) => ids,
+        Err(ClError(CL_DEVICE_NOT_FOUND)) => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut res = vec![];
+
+    for id in ids {
+        let device = Device::from(id);
+        let matches = match device_type {
+            DeviceType::DGpu => device.host_unified_memory()?,
+            DeviceType::IGpu => !device.host_unified_memory()?,
+            DeviceType::Gpu | DeviceType::Cpu | DeviceType::All => true,
+        };
+
+        if matches {
+            res.push(device);
+        }
+    }
+
+    Ok(res)
+}
@@ -1,158 +1,120 @@
-use opencl3::command_queue::CommandQueue;
-use opencl3::command_queue::CL_QUEUE_PROFILING_ENABLE;
-use opencl3::context::Context;
 use opencl3::device::Device;
-use opencl3::error_codes::ClError;
-use opencl3::kernel::Kernel;
-use opencl3::memory::create_buffer;
-use opencl3::memory::Buffer;
-use opencl3::memory::{CL_MEM_READ_ONLY, CL_MEM_WRITE_ONLY};
-use opencl3::platform::Platform;
-use opencl3::program::Program;
-use opencl3::program::CL_STD_3_0;
-use opencl3::types::cl_float;
-use opencl3::types::{CL_FALSE, CL_TRUE};
-
-use crate::args::DeviceType;
+
+use crate::cache::ProgramCache;
+use crate::mode::{DeviceSelector, DeviceType};
+use crate::multiplier::{check_batches, Gemm, StridedBatch};
 use crate::multiplier::{Multiplier, MultiplierInfo, MultiplierStat};
 use crate::sources;
+use crate::Element;
 use crate::Matrix;
 use crate::Result;
 
+use super::runtime::{Operand, Problem, Runtime, WorkSizes};
 use super::TimeStat;
 
-#[derive(Clone)]
 pub struct EasyMultiplier {
-    device: Device,
+    runtime: Runtime,
     stat: Option<TimeStat>,
 }
 
 impl EasyMultiplier {
     pub fn new(device_type: DeviceType, index: usize) -> Result<Self> {
-        let device = super::get_device(device_type, index)?;
-
-        Ok(Self { device, stat: None })
+        Self::from_selector(&DeviceSelector::new(device_type, index))
     }
 
-    fn platform(&self) -> Result<Platform> {
-        let platform = self.device.platform()?;
-        Ok(Platform::new(platform))
+    /// Runs on the device picked by `selector`
+    pub fn from_selector(selector: &DeviceSelector) -> Result<Self> {
+        Self::on_device(super::devices::select(selector)?)
     }
-}
-
-impl Multiplier for EasyMultiplier {
-    fn multiply(&mut self, m1: &Matrix, m2: &Matrix) -> Result<Matrix> {
-        let context = Context::from_device(&self.device)?;
-        let queue =
-            CommandQueue::create_default_with_properties(&context, CL_QUEUE_PROFILING_ENABLE, 0)?;
-
-        let m1_size = std::mem::size_of::<cl_float>() * m1.rows * m1.cols;
-        let mut m1_buf: Buffer<cl_float> = unsafe {
-            let mem = create_buffer(
-                context.get(),
-                CL_MEM_READ_ONLY,
-                m1_size,
-                std::ptr::null_mut(),
-            )
-            .map_err(ClError)?;
-            Buffer::new(mem)
-        };
 
-        let m2_size = std::mem::size_of::<cl_float>() * m2.rows * m2.cols;
-        let mut m2_buf: Buffer<cl_float> = unsafe {
-            let mem = create_buffer(
-                context.get(),
-                CL_MEM_READ_ONLY,
-                m2_size,
-                std::ptr::null_mut(),
-            )
-            .map_err(ClError)?;
-            Buffer::new(mem)
-        };
-
-        let mut res = Matrix::create_empty(m1.rows, m2.cols);
-
-        let m3_size = std::mem::size_of::<cl_float>() * m1.rows * m2.cols;
-        let mut m3_buf: Buffer<cl_float> = unsafe {
-            let mem = create_buffer(
-                context.get(),
-                CL_MEM_WRITE_ONLY,
-                m3_size,
-                std::ptr::null_mut(),
-            )
-            .map_err(ClError)?;
-            Buffer::new(mem)
-        };
+    pub(super) fn on_device(device: Device) -> Result<Self> {
+        let runtime = Runtime::new(device, sources::EASY_SOURCE)?;
 
-        let write_event1 =
-            unsafe { queue.enqueue_write_buffer(&mut m1_buf, CL_FALSE, 0, m1.data.as_ref(), &[])? };
+        Ok(Self {
+            runtime,
+            stat: None,
+        })
+    }
 
-        let write_event2 =
-            unsafe { queue.enqueue_write_buffer(&mut m2_buf, CL_FALSE, 0, m2.data.as_ref(), &[])? };
+    /// Use `program_cache` instead of the default one when building kernels
+    pub fn with_program_cache(mut self, program_cache: ProgramCache) -> Self {
+        self.runtime.set_program_cache(program_cache);
+        self
+    }
+}
 
-        let write_event3 = unsafe {
-            queue.enqueue_write_buffer(&mut m3_buf, CL_FALSE, 0, res.data.as_ref(), &[])?
+impl<T: Element> Multiplier<T> for EasyMultiplier {
+    fn gemm(
+        &mut self,
+        params: Gemm<T>,
+        a: &Matrix<T>,
+        b: &Matrix<T>,
+        c: &mut Matrix<T>,
+    ) -> Result<()> {
+        super::check_gemm_dimensions(&params, a, b, c)?;
+
+        let problem = Problem::single(c.rows, c.cols, params.trans_a.shape(a).1);
+        let work_sizes = WorkSizes {
+            global: [c.cols, c.rows],
+            local: None,
         };
+        let stat = self.runtime.gemm(
+            &params,
+            &problem,
+            Operand::from(&a.data),
+            Operand::from(&b.data),
+            &mut c.data,
+            work_sizes,
+        )?;
+
+        self.stat = Some(stat);
+
+        Ok(())
+    }
 
-        let program =
-            Program::create_and_build_from_source(&context, sources::EASY_SOURCE, CL_STD_3_0)?;
-        let kernel = Kernel::create(&program, sources::KERNEL_NAME)?;
-
-        unsafe {
-            kernel.set_arg(0, &m1_buf)?;
-            kernel.set_arg(1, &m2_buf)?;
-            kernel.set_arg(2, &m3_buf)?;
-            kernel.set_arg(3, &m2.cols)?;
-            kernel.set_arg(4, &m1.rows)?;
-            kernel.set_arg(5, &m1.cols)?;
-        }
-
-        let kernel_event = unsafe {
-            let global_work_sizes = [m2.cols, m1.rows];
-            queue.enqueue_nd_range_kernel(
-                kernel.get(),
-                2,
-                std::ptr::null_mut(),
-                global_work_sizes.as_ptr(),
-                std::ptr::null_mut(),
-                &[],
-            )?
+    fn multiply_strided_batch(
+        &mut self,
+        a: &StridedBatch<T>,
+        b: &StridedBatch<T>,
+    ) -> Result<Vec<T>> {
+        check_batches(a, b)?;
+
+        let problem = Problem {
+            n: b.cols,
+            m: a.rows,
+            k: a.cols,
+            count: a.count,
+            strides: [a.stride, b.stride, a.rows * b.cols],
+        };
+        let work_sizes = WorkSizes {
+            global: [b.cols, a.rows],
+            local: None,
         };
-        let read_event =
-            unsafe { queue.enqueue_read_buffer(&m3_buf, CL_TRUE, 0, &mut res.data, &[])? };
-
-        let read_time = super::get_profiling_info(read_event)?;
-        let kernel_time = super::get_profiling_info(kernel_event)?;
-        let write_time1 = super::get_profiling_info(write_event1)?;
-        let write_time2 = super::get_profiling_info(write_event2)?;
-        let write_time3 = super::get_profiling_info(write_event3)?;
 
-        let total_time = write_time1 + write_time2 + write_time3 + kernel_time + read_time;
+        let mut res = vec![T::default(); a.count * a.rows * b.cols];
+        let stat = self.runtime.gemm(
+            &Gemm::default(),
+            &problem,
+            a.used().into(),
+            b.used().into(),
+            &mut res,
+            work_sizes,
+        )?;
 
-        self.stat = Some(TimeStat {
-            total_time,
-            kernel_time,
-        });
+        self.stat = Some(stat);
 
         Ok(res)
     }
 
     fn info(&self) -> Result<MultiplierInfo> {
-        let device_name = self.device.name()?;
-        let platform_name = self.platform()?.name()?;
-
-        let res = MultiplierInfo::OpenClMultiplier {
-            device_name,
-            platform_name,
-        };
-
-        Ok(res)
+        self.runtime.info()
     }
 
     fn stat(&self) -> Option<MultiplierStat> {
         self.stat.map(|stat| MultiplierStat {
             total_time: stat.total_time,
             gpu_time: stat.kernel_time,
+            devices: vec![],
         })
     }
 }
@@ -1,167 +1,190 @@
-use opencl3::command_queue::CommandQueue;
-use opencl3::command_queue::CL_QUEUE_PROFILING_ENABLE;
-use opencl3::context::Context;
 use opencl3::device::Device;
-use opencl3::error_codes::ClError;
-use opencl3::kernel::Kernel;
-use opencl3::memory::create_buffer;
-use opencl3::memory::Buffer;
-use opencl3::memory::{CL_MEM_READ_ONLY, CL_MEM_WRITE_ONLY};
-use opencl3::platform::Platform;
-use opencl3::program::Program;
-use opencl3::program::CL_STD_3_0;
-use opencl3::types::cl_float;
-use opencl3::types::{CL_FALSE, CL_TRUE};
-
-use crate::args::DeviceType;
+
+use crate::cache::ProgramCache;
+use crate::mode::{DeviceSelector, DeviceType};
+use crate::multiplier::{check_batches, Gemm, StridedBatch};
 use crate::multiplier::{Multiplier, MultiplierInfo, MultiplierStat};
 use crate::sources;
+use crate::sources::KernelConfig;
+use crate::tuning::{self, TuningDatabase};
+use crate::Element;
+use crate::MatmulError;
 use crate::Matrix;
 use crate::Result;
 
+use super::runtime::{Operand, Problem, Runtime, WorkSizes};
 use super::TimeStat;
 
+/// Multiplier that runs [sources::HARD_MUL] built with the [KernelConfig] of its device
+///
+/// The config is looked up in a [TuningDatabase] when the multiplier is created, devices that were
+/// never tuned use [KernelConfig::default]
 pub struct HardMultiplier {
-    device: Device,
+    runtime: Runtime,
+    config: KernelConfig,
     stat: Option<TimeStat>,
 }
 
 impl HardMultiplier {
     pub fn new(device_type: DeviceType, index: usize) -> Result<Self> {
-        let device = super::get_device(device_type, index)?;
-
-        Ok(Self { device, stat: None })
+        Self::from_selector(&DeviceSelector::new(device_type, index))
     }
 
-    fn platform(&self) -> Result<Platform> {
-        let platform = self.device.platform()?;
-        Ok(Platform::new(platform))
+    /// Runs on the device picked by `selector`, with its config in the default [TuningDatabase]
+    pub fn from_selector(selector: &DeviceSelector) -> Result<Self> {
+        Self::from_tuning_database(selector, &TuningDatabase::default())
     }
-}
 
-impl Multiplier for HardMultiplier {
-    fn multiply(&mut self, m1: &Matrix, m2: &Matrix) -> Result<Matrix> {
-        let orig_rows = m1.rows;
-        let orig_cols = m2.cols;
-
-        let m1 = m1.create_zero_padded(sources::TILE);
-        let m2 = m2.create_zero_padded(sources::TILE);
-
-        let context = Context::from_device(&self.device)?;
-        let queue =
-            CommandQueue::create_default_with_properties(&context, CL_QUEUE_PROFILING_ENABLE, 0)?;
-
-        let m1_size = std::mem::size_of::<cl_float>() * m1.rows * m1.cols;
-        let mut m1_buf: Buffer<cl_float> = unsafe {
-            let mem = create_buffer(
-                context.get(),
-                CL_MEM_READ_ONLY,
-                m1_size,
-                std::ptr::null_mut(),
-            )
-            .map_err(ClError)?;
-            Buffer::new(mem)
-        };
+    /// Runs on the device picked by `selector`, with its config in `tuning_database`
+    pub fn from_tuning_database(
+        selector: &DeviceSelector,
+        tuning_database: &TuningDatabase,
+    ) -> Result<Self> {
+        let device = super::devices::select(selector)?;
+        let config = tuning_database.config(&device);
+        Self::on_device(device, config)
+    }
 
-        let m2_size = std::mem::size_of::<cl_float>() * m2.rows * m2.cols;
-        let mut m2_buf: Buffer<cl_float> = unsafe {
-            let mem = create_buffer(
-                context.get(),
-                CL_MEM_READ_ONLY,
-                m2_size,
-                std::ptr::null_mut(),
-            )
-            .map_err(ClError)?;
-            Buffer::new(mem)
-        };
+    pub(super) fn on_device(device: Device, config: KernelConfig) -> Result<Self> {
+        let mut runtime = Runtime::new(device, sources::HARD_MUL)?;
+        runtime.set_defines(config.defines());
 
-        let mut res = Matrix::create_empty(m1.rows, m2.cols);
-
-        let m3_size = std::mem::size_of::<cl_float>() * m1.rows * m2.cols;
-        let mut m3_buf: Buffer<cl_float> = unsafe {
-            let mem = create_buffer(
-                context.get(),
-                CL_MEM_WRITE_ONLY,
-                m3_size,
-                std::ptr::null_mut(),
-            )
-            .map_err(ClError)?;
-            Buffer::new(mem)
-        };
+        Ok(Self {
+            runtime,
+            config,
+            stat: None,
+        })
+    }
 
-        let write_event1 =
-            unsafe { queue.enqueue_write_buffer(&mut m1_buf, CL_FALSE, 0, m1.data.as_ref(), &[])? };
+    /// Use `program_cache` instead of the default one when building kernels
+    pub fn with_program_cache(mut self, program_cache: ProgramCache) -> Self {
+        self.runtime.set_program_cache(program_cache);
+        self
+    }
 
-        let write_event2 =
-            unsafe { queue.enqueue_write_buffer(&mut m2_buf, CL_FALSE, 0, m2.data.as_ref(), &[])? };
+    /// Build the kernel with `config` instead of the tuned one
+    ///
+    /// Fails if the config is not [valid](KernelConfig::is_valid) or the device can not run it
+    /// with the widest accumulators
+    pub fn with_config(mut self, config: KernelConfig) -> Result<Self> {
+        let device = self.runtime.device();
+        if !config.is_valid() || !tuning::fits(config, device, tuning::MAX_ACC_SIZE) {
+            return Err(MatmulError::InvalidConfig {
+                config,
+                device: device.name()?,
+            });
+        }
 
-        let write_event3 = unsafe {
-            queue.enqueue_write_buffer(&mut m3_buf, CL_FALSE, 0, res.data.as_ref(), &[])?
-        };
+        self.runtime.set_defines(config.defines());
+        self.config = config;
+        Ok(self)
+    }
 
-        let program =
-            Program::create_and_build_from_source(&context, sources::HARD_MUL, CL_STD_3_0)?;
-        let kernel = Kernel::create(&program, sources::KERNEL_NAME)?;
-
-        unsafe {
-            kernel.set_arg(0, &m1_buf)?;
-            kernel.set_arg(1, &m2_buf)?;
-            kernel.set_arg(2, &m3_buf)?;
-            kernel.set_arg(3, &m2.cols)?;
-            kernel.set_arg(4, &m1.rows)?;
-            kernel.set_arg(5, &m1.cols)?;
-        }
+    /// Config the kernel is built with
+    pub fn config(&self) -> KernelConfig {
+        self.config
+    }
+}
 
-        let kernel_event = unsafe {
-            let global_work_sizes = [m2.cols, m1.rows / sources::ELEM_PER_THREAD];
-            let local_work_size = [sources::TILE, sources::TILE / sources::ELEM_PER_THREAD];
-            queue.enqueue_nd_range_kernel(
-                kernel.get(),
-                2,
-                std::ptr::null_mut(),
-                global_work_sizes.as_ptr(),
-                local_work_size.as_ptr(),
-                &[],
-            )?
+impl<T: Element> Multiplier<T> for HardMultiplier {
+    fn gemm(
+        &mut self,
+        params: Gemm<T>,
+        a: &Matrix<T>,
+        b: &Matrix<T>,
+        c: &mut Matrix<T>,
+    ) -> Result<()> {
+        super::check_gemm_dimensions(&params, a, b, c)?;
+
+        let KernelConfig {
+            tile,
+            elem_per_thread,
+            ..
+        } = self.config;
+
+        let (mut padded_a, mut padded_b) = (None, None);
+        let a = super::zero_padded(a, tile, &mut padded_a);
+        let b = super::zero_padded(b, tile, &mut padded_b);
+        let mut padded_c = c.create_zero_padded(tile);
+
+        let problem = Problem::single(padded_c.rows, padded_c.cols, params.trans_a.shape(a).1);
+        let work_sizes = WorkSizes {
+            global: [padded_c.cols, padded_c.rows / elem_per_thread],
+            local: Some([tile, tile / elem_per_thread]),
         };
+        let stat = self.runtime.gemm(
+            &params,
+            &problem,
+            Operand::from(&a.data),
+            Operand::from(&b.data),
+            &mut padded_c.data,
+            work_sizes,
+        )?;
 
-        let read_event =
-            unsafe { queue.enqueue_read_buffer(&m3_buf, CL_TRUE, 0, &mut res.data, &[])? };
+        self.stat = Some(stat);
 
-        let read_time = super::get_profiling_info(read_event)?;
-        let kernel_time = super::get_profiling_info(kernel_event)?;
-        let write_time1 = super::get_profiling_info(write_event1)?;
-        let write_time2 = super::get_profiling_info(write_event2)?;
-        let write_time3 = super::get_profiling_info(write_event3)?;
+        *c = padded_c.create_trimmed(c.rows, c.cols);
 
-        let total_time = write_time1 + write_time2 + write_time3 + kernel_time + read_time;
+        Ok(())
+    }
 
-        self.stat = Some(TimeStat {
-            total_time,
-            kernel_time,
-        });
+    fn multiply_strided_batch(
+        &mut self,
+        a: &StridedBatch<T>,
+        b: &StridedBatch<T>,
+    ) -> Result<Vec<T>> {
+        check_batches(a, b)?;
+
+        let KernelConfig {
+            tile,
+            elem_per_thread,
+            ..
+        } = self.config;
+
+        let (padded_a_data, padded_a) = super::pad_batch(a, tile)?;
+        let (padded_b_data, padded_b) = super::pad_batch(b, tile)?;
+        let padded_c = (padded_a.0, padded_b.1);
+
+        let problem = Problem {
+            n: padded_b.1,
+            m: padded_a.0,
+            k: padded_a.1,
+            count: a.count,
+            strides: [
+                padded_a.0 * padded_a.1,
+                padded_b.0 * padded_b.1,
+                padded_c.0 * padded_c.1,
+            ],
+        };
+        let work_sizes = WorkSizes {
+            global: [padded_b.1, padded_a.0 / elem_per_thread],
+            local: Some([tile, tile / elem_per_thread]),
+        };
+
+        let mut res = vec![T::default(); a.count * padded_c.0 * padded_c.1];
+        let stat = self.runtime.gemm(
+            &Gemm::default(),
+            &problem,
+            padded_a_data.as_slice().into(),
+            padded_b_data.as_slice().into(),
+            &mut res,
+            work_sizes,
+        )?;
 
-        let res = res.create_trimmed(orig_rows, orig_cols);
+        self.stat = Some(stat);
 
-        Ok(res)
+        Ok(super::trim_batch(res, padded_c, a.rows, b.cols))
     }
 
     fn info(&self) -> Result<MultiplierInfo> {
-        let device_name = self.device.name()?;
-        let platform_name = self.platform()?.name()?;
-
-        let res = MultiplierInfo::OpenClMultiplier {
-            device_name,
-            platform_name,
-        };
-
-        Ok(res)
+        self.runtime.info()
     }
 
     fn stat(&self) -> Option<MultiplierStat> {
         self.stat.map(|stat| MultiplierStat {
             total_time: stat.total_time,
             gpu_time: stat.kernel_time,
+            devices: vec![],
         })
     }
 }
@@ -1,6 +1,3 @@
-use std::io;
-
-use opencl3::device::get_all_devices;
 use opencl3::device::Device;
 use opencl3::device::{CL_DEVICE_TYPE_ALL, CL_DEVICE_TYPE_CPU, CL_DEVICE_TYPE_GPU};
 use opencl3::error_codes::ClError;
@@ -9,21 +6,38 @@ use opencl3::event::Event;
 use opencl3::event::{CL_PROFILING_COMMAND_END, CL_PROFILING_COMMAND_START};
 use opencl3::types::cl_device_type;
 
-use super::args::DeviceType;
+use super::mode::DeviceType;
+use super::multiplier::{Gemm, StridedBatch};
+use super::Element;
+use super::MatmulError;
+use super::Matrix;
 use super::Result;
 
 mod basic;
+mod cpu;
+mod devices;
 mod easy;
 mod hard;
 mod medium;
+mod multi;
+mod runtime;
+mod simd;
+mod strassen;
+mod tuner;
 #[rustfmt::skip]
 #[cfg(test)]
 mod tests;
 
 pub use basic::BasicMultiplier;
+pub use cpu::CpuMultiplier;
+pub use devices::{platforms, DeviceInfo, PlatformInfo};
 pub use easy::EasyMultiplier;
 pub use hard::HardMultiplier;
 pub use medium::MediumMultiplier;
+pub use multi::MultiDeviceMultiplier;
+pub use simd::{Isa, SimdMultiplier};
+pub use strassen::StrassenMultiplier;
+pub use tuner::{tune, Tuning, TuningResult};
 
 #[derive(Clone, Copy)]
 struct TimeStat {
@@ -54,71 +68,111 @@ fn get_profiling_info(event: Event) -> Result<u64> {
     Ok(res)
 }
 
-fn get_device(device_type: DeviceType, device_index: usize) -> Result<Device> {
-    let devices = get_all_devices(device_type.into())?
-        .iter()
-        .map(|id| Device::from(*id))
-        .collect::<Vec<_>>();
+/// Makes sure that `op(a) * op(b)` can be computed and stored in `c`
+fn check_gemm_dimensions<T: Element>(
+    params: &Gemm<T>,
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+    c: &Matrix<T>,
+) -> Result<()> {
+    let left = params.trans_a.shape(a);
+    let right = params.trans_b.shape(b);
+
+    if left.1 != right.0 {
+        return Err(MatmulError::DimensionMismatch { left, right });
+    }
 
-    if device_type == DeviceType::All {
-        let device = devices.get(device_index).ok_or_else(|| {
-            let msg = format!("invalid `device_index`: {device_index}, out of bounds");
-            io::Error::new(io::ErrorKind::Other, msg)
-        })?;
+    if (c.rows, c.cols) != (left.0, right.1) {
+        return Err(MatmulError::OutputMismatch {
+            expected: (left.0, right.1),
+            actual: (c.rows, c.cols),
+        });
+    }
+
+    Ok(())
+}
 
-        return Ok(*device);
+/// `alpha * sum + beta * c`, `c` is ignored if `beta` is zero
+fn scale<T: Element>(params: &Gemm<T>, sum: T::Acc, c: T) -> T {
+    let mut res = params.alpha.to_acc().wrapping_mul(sum);
+    if params.beta != T::default() {
+        res = res.wrapping_add(params.beta.to_acc().wrapping_mul(c.to_acc()));
     }
 
-    let mut filter_dev_type = vec![];
+    T::from_acc(res)
+}
 
-    for device in devices.iter() {
-        if device.dev_type()? == device_type.into() {
-            filter_dev_type.push(*device);
-        }
+/// `matrix` zero padded to multiples of `tile`, see [Matrix::create_zero_padded]
+///
+/// Matrices that need no padding are returned as is instead of being copied into `padded`, so
+/// memory mapped matrices stay mapped
+fn zero_padded<'a, T: Element>(
+    matrix: &'a Matrix<T>,
+    tile: usize,
+    padded: &'a mut Option<Matrix<T>>,
+) -> &'a Matrix<T> {
+    let pad = |dim: usize| dim.next_multiple_of(tile).max(tile);
+    if pad(matrix.rows) == matrix.rows && pad(matrix.cols) == matrix.cols {
+        return matrix;
     }
 
-    match device_type {
-        DeviceType::DGpu => {
-            let mut filter_unified_memory = vec![];
+    padded.insert(matrix.create_zero_padded(tile))
+}
 
-            for &device in devices.iter() {
-                let unified_mem = device.host_unified_memory()?;
-                if unified_mem {
-                    filter_unified_memory.push(device);
-                }
-            }
+/// Copies every matrix of `batch` zero padded to multiples of `tile`, the copies are stored back to
+/// back
+///
+/// Returns the data and the padded `(rows, cols)` of each matrix
+fn pad_batch<T: Element>(batch: &StridedBatch<T>, tile: usize) -> Result<(Vec<T>, (usize, usize))> {
+    let mut data = vec![];
+    let mut shape = (0, 0);
+
+    for index in 0..batch.count {
+        let matrix = Matrix::create(batch.rows, batch.cols, batch.get(index))?;
+        let padded = matrix.create_zero_padded(tile);
+        shape = (padded.rows, padded.cols);
+        data.extend_from_slice(&padded.data);
+    }
 
-            let res = filter_unified_memory.get(device_index).ok_or_else(|| {
-                let msg = format!("invalid `device_index`: {device_index}, out of bounds");
-                io::Error::new(io::ErrorKind::Other, msg)
-            })?;
+    Ok((data, shape))
+}
 
-            Ok(*res)
-        }
-        DeviceType::IGpu => {
-            let mut filter_unified_memory = vec![];
-
-            for &device in devices.iter() {
-                let unified_mem = device.host_unified_memory()?;
-                if !unified_mem {
-                    filter_unified_memory.push(device);
-                }
-            }
-
-            let res = filter_unified_memory.get(device_index).ok_or_else(|| {
-                let msg = format!("invalid `device_index`: {device_index}, out of bounds");
-                io::Error::new(io::ErrorKind::Other, msg)
-            })?;
-
-            Ok(*res)
-        }
-        _ => {
-            let res = filter_dev_type.get(device_index).ok_or_else(|| {
-                let msg = format!("invalid `device_index`: {device_index}, out of bounds");
-                io::Error::new(io::ErrorKind::Other, msg)
-            })?;
+/// Reverse of [pad_batch], trims every padded matrix in `data` to `rows x cols`
+fn trim_batch<T: Element>(
+    data: Vec<T>,
+    padded: (usize, usize),
+    rows: usize,
+    cols: usize,
+) -> Vec<T> {
+    let (padded_rows, padded_cols) = padded;
+    if padded_rows * padded_cols == 0 {
+        return vec![];
+    }
 
-            Ok(*res)
-        }
+    data.chunks(padded_rows * padded_cols)
+        .flat_map(|chunk| {
+            let matrix = Matrix {
+                rows: padded_rows,
+                cols: padded_cols,
+                data: chunk.to_vec().into(),
+            };
+            matrix.create_trimmed(rows, cols).data.into_vec()
+        })
+        .collect()
+}
+
+/// Makes sure that `device` can run kernels that work with `T`
+fn check_support<T: Element>(device: &Device) -> Result<()> {
+    let Some(extension) = T::CL_EXTENSION else {
+        return Ok(());
+    };
+
+    if !device.extensions()?.split(' ').any(|ext| ext == extension) {
+        return Err(MatmulError::UnsupportedType {
+            name: T::NAME,
+            device: device.name()?,
+        });
     }
+
+    Ok(())
 }
@@ -0,0 +1,282 @@
+use std::panic;
+use std::thread;
+use std::time;
+
+use crate::mode::{DeviceSelector, Kernel};
+use crate::multiplier::{DeviceStat, Gemm, Options, Transpose};
+use crate::multiplier::{Multiplier, MultiplierInfo, MultiplierStat};
+use crate::sources;
+use crate::Element;
+use crate::MatmulError;
+use crate::Matrix;
+use crate::Result;
+
+use super::{EasyMultiplier, HardMultiplier, MediumMultiplier};
+
+/// Multiplier that computes a block of rows of the result on each of its parts
+///
+/// Parts run concurrently, each on its own thread. The first multiplication splits the rows
+/// equally, later ones in proportion to the rows per second that each part achieved on the
+/// previous one, so faster devices get more work. Blocks are multiples of the tiles of every part
+/// so that the OpenCl implementations do not pad them, [sources::TILE] for parts given to
+/// [MultiDeviceMultiplier::new]
+pub struct MultiDeviceMultiplier<T: Element> {
+    parts: Vec<Part<T>>,
+    /// Rows that every block but the last one is a multiple of
+    align: usize,
+    stat: Option<MultiplierStat>,
+}
+
+struct Part<T: Element> {
+    multiplier: Box<dyn Multiplier<T> + Send>,
+    info: MultiplierInfo,
+    /// Rows per second of the last multiplication, relative weight of the part
+    throughput: f64,
+}
+
+impl<T: Element> MultiDeviceMultiplier<T> {
+    /// Multiplier that splits the rows of the result between `parts`
+    ///
+    /// Can panic if `parts` is empty
+    pub fn new(parts: Vec<Box<dyn Multiplier<T> + Send>>) -> Result<Self> {
+        assert!(!parts.is_empty(), "multiplier needs at least one part");
+
+        let parts = parts
+            .into_iter()
+            .map(|multiplier| {
+                Ok(Part {
+                    info: multiplier.info()?,
+                    multiplier,
+                    throughput: 1.0,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            parts,
+            align: sources::TILE,
+            stat: None,
+        })
+    }
+
+    /// Runs `kernel` on every device picked by [select_all](super::devices::select_all), with
+    /// programs cached and kernel configs looked up as `options` say
+    pub fn from_selector(
+        kernel: Kernel,
+        selector: &DeviceSelector,
+        options: &Options,
+    ) -> Result<Self> {
+        let devices = super::devices::select_all(selector)?;
+        if devices.is_empty() {
+            return Err(MatmulError::NoDevice(selector.clone()));
+        }
+
+        let mut parts: Vec<Box<dyn Multiplier<T> + Send>> = vec![];
+        let mut align = sources::TILE;
+        for device in devices {
+            let program_cache = options.program_cache.clone();
+            let part: Box<dyn Multiplier<T> + Send> = match kernel {
+                Kernel::Easy => {
+                    Box::new(EasyMultiplier::on_device(device)?.with_program_cache(program_cache))
+                }
+                Kernel::Medium => {
+                    Box::new(MediumMultiplier::on_device(device)?.with_program_cache(program_cache))
+                }
+                Kernel::Hard => {
+                    let config = options.tuning_database.config(&device);
+                    align = lcm(align, config.tile);
+                    Box::new(
+                        HardMultiplier::on_device(device, config)?
+                            .with_program_cache(program_cache),
+                    )
+                }
+            };
+            parts.push(part);
+        }
+
+        Ok(Self {
+            align,
+            ..Self::new(parts)?
+        })
+    }
+}
+
+impl<T: Element> Multiplier<T> for MultiDeviceMultiplier<T> {
+    fn gemm(
+        &mut self,
+        params: Gemm<T>,
+        a: &Matrix<T>,
+        b: &Matrix<T>,
+        c: &mut Matrix<T>,
+    ) -> Result<()> {
+        let instant = time::Instant::now();
+        super::check_gemm_dimensions(&params, a, b, c)?;
+
+        let weights = self
+            .parts
+            .iter()
+            .map(|part| part.throughput)
+            .collect::<Vec<_>>();
+        let sizes = split(c.rows, &weights, self.align);
+
+        // every part gets its rows of `op(a)` as is
+        let part_params = Gemm {
+            trans_a: Transpose::No,
+            ..params
+        };
+
+        let mut blocks = vec![];
+        let mut first_row = 0;
+        for &rows in &sizes {
+            let block_a = rows_of(a, params.trans_a, first_row, rows);
+            let block_c = if params.beta == T::default() {
+                Matrix::create_empty(rows, c.cols)
+            } else {
+                Matrix::create(rows, c.cols, &c.data[first_row * c.cols..][..rows * c.cols])?
+            };
+            blocks.push((block_a, block_c));
+            first_row += rows;
+        }
+
+        let elapsed = thread::scope(|scope| {
+            let handles = self
+                .parts
+                .iter_mut()
+                .zip(&mut blocks)
+                .filter(|(_, (_, block_c))| block_c.rows > 0)
+                .map(|(part, (block_a, block_c))| {
+                    scope.spawn(move || {
+                        let instant = time::Instant::now();
+                        part.multiplier.gemm(part_params, block_a, b, block_c)?;
+                        Ok(instant.elapsed())
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        let mut first_row = 0;
+        for (_, block_c) in &blocks {
+            let len = block_c.rows * c.cols;
+            c.data[first_row * c.cols..][..len].copy_from_slice(&block_c.data);
+            first_row += block_c.rows;
+        }
+
+        let mut devices = vec![];
+        let mut gpu_time = 0;
+        let busy = self
+            .parts
+            .iter_mut()
+            .zip(&sizes)
+            .filter(|(_, &rows)| rows > 0);
+        for ((part, &rows), elapsed) in busy.zip(elapsed) {
+            let seconds = elapsed.as_secs_f64();
+            if seconds > 0.0 {
+                part.throughput = rows as f64 / seconds;
+            }
+
+            let part_gpu_time = part.multiplier.stat().map_or(0, |stat| stat.gpu_time);
+            gpu_time = gpu_time.max(part_gpu_time);
+
+            devices.push(DeviceStat {
+                name: device_name(&part.info),
+                rows,
+                total_time: elapsed.as_millis() as u64,
+                gpu_time: part_gpu_time,
+            });
+        }
+
+        self.stat = Some(MultiplierStat {
+            total_time: instant.elapsed().as_millis() as u64,
+            gpu_time,
+            devices,
+        });
+
+        Ok(())
+    }
+
+    fn info(&self) -> Result<MultiplierInfo> {
+        let devices = self.parts.iter().map(|part| part.info.clone()).collect();
+
+        Ok(MultiplierInfo::MultiDeviceMultiplier { devices })
+    }
+
+    /// Kernel time is the one of the slowest part, since parts run concurrently
+    fn stat(&self) -> Option<MultiplierStat> {
+        self.stat.clone()
+    }
+}
+
+/// Number of rows of each part, in proportion to `weights` and in multiples of `align` but for the
+/// last nonempty block
+fn split(rows: usize, weights: &[f64], align: usize) -> Vec<usize> {
+    let total = weights.iter().sum::<f64>();
+    let mut sum = 0.0;
+    let mut first_row = 0;
+
+    weights
+        .iter()
+        .enumerate()
+        .map(|(index, weight)| {
+            sum += weight;
+            let end = if index + 1 == weights.len() {
+                rows
+            } else {
+                let end = (rows as f64 * sum / total / align as f64).round() as usize * align;
+                end.clamp(first_row, rows)
+            };
+
+            let size = end - first_row;
+            first_row = end;
+            size
+        })
+        .collect()
+}
+
+/// Least common multiple of two tiles
+fn lcm(a: usize, b: usize) -> usize {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+
+    a / x * b
+}
+
+/// Copy of `rows` rows of `op(matrix)` starting at `first_row`
+fn rows_of<T: Element>(
+    matrix: &Matrix<T>,
+    trans: Transpose,
+    first_row: usize,
+    rows: usize,
+) -> Matrix<T> {
+    let cols = trans.shape(matrix).1;
+    let mut res = Matrix::create_empty(rows, cols);
+
+    for i in 0..rows {
+        for j in 0..cols {
+            let value = match trans {
+                Transpose::No => matrix.get(first_row + i, j),
+                Transpose::Yes => matrix.get(j, first_row + i),
+            };
+            res.set(i, j, value);
+        }
+    }
+
+    res
+}
+
+/// Name that a part is reported under in [DeviceStat]
+fn device_name(info: &MultiplierInfo) -> String {
+    match info {
+        MultiplierInfo::OpenClMultiplier { device_name, .. } => device_name.clone(),
+        MultiplierInfo::SimdMultiplier { isa } => format!("host ({isa})"),
+        MultiplierInfo::OnDeviceMultiplier | MultiplierInfo::MultiDeviceMultiplier { .. } => {
+            "host".to_string()
+        }
+    }
+}
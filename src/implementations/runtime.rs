@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+
+use opencl3::command_queue::CommandQueue;
+use opencl3::command_queue::CL_QUEUE_PROFILING_ENABLE;
+use opencl3::context::Context;
+use opencl3::device::Device;
+use opencl3::error_codes::ClError;
+use opencl3::event::Event;
+use opencl3::kernel::Kernel;
+use opencl3::memory::create_buffer;
+use opencl3::memory::Buffer;
+use opencl3::memory::{CL_MEM_READ_ONLY, CL_MEM_READ_WRITE, CL_MEM_USE_HOST_PTR};
+use opencl3::platform::Platform;
+use opencl3::program::CL_STD_3_0;
+use opencl3::types::cl_mem_flags;
+use opencl3::types::cl_uint;
+use opencl3::types::{CL_FALSE, CL_TRUE};
+
+use crate::cache::ProgramCache;
+use crate::element::{as_bytes, as_bytes_mut};
+use crate::multiplier::{Gemm, MultiplierInfo};
+use crate::sources;
+use crate::Element;
+use crate::MatmulError;
+use crate::Result;
+use crate::Storage;
+
+use super::TimeStat;
+
+/// Device buffers, in order: first operand, second operand, result
+const BUFFER_FLAGS: [cl_mem_flags; 3] = [CL_MEM_READ_ONLY, CL_MEM_READ_ONLY, CL_MEM_READ_WRITE];
+
+/// Shape of a batch of multiplications, see [sources::GEMM_DEFINES]
+pub(super) struct Problem {
+    /// Columns of each result
+    pub n: usize,
+    /// Rows of each result
+    pub m: usize,
+    /// Inner dimension of each product
+    pub k: usize,
+    /// Number of multiplications in the batch
+    pub count: usize,
+    /// Distance in elements between consecutive matrices of `a`, `b` and `c`
+    pub strides: [usize; 3],
+}
+
+impl Problem {
+    /// A single `m x k` by `k x n` multiplication
+    pub fn single(m: usize, n: usize, k: usize) -> Self {
+        Self {
+            n,
+            m,
+            k,
+            count: 1,
+            strides: [0; 3],
+        }
+    }
+}
+
+/// Work sizes of the first two dimensions of a launch, the third one is the batch
+pub(super) struct WorkSizes {
+    pub global: [usize; 2],
+    pub local: Option<[usize; 2]>,
+}
+
+/// Operand of a launch
+pub(super) enum Operand<'a, T> {
+    /// Copied to a device buffer that is reused by later launches
+    Copied(&'a [T]),
+    /// Used by the device in place with `CL_MEM_USE_HOST_PTR`, so memory mapped matrices are
+    /// never copied in host memory and integrated devices can read them without any copy at all
+    InPlace(&'a [T]),
+}
+
+impl<T> Operand<'_, T> {
+    fn data(&self) -> &[T] {
+        match self {
+            Self::Copied(data) | Self::InPlace(data) => data,
+        }
+    }
+}
+
+impl<'a, T> From<&'a [T]> for Operand<'a, T> {
+    fn from(data: &'a [T]) -> Self {
+        Self::Copied(data)
+    }
+}
+
+impl<'a, T: Element> From<&'a Storage<T>> for Operand<'a, T> {
+    fn from(data: &'a Storage<T>) -> Self {
+        if data.is_mapped() {
+            Self::InPlace(data)
+        } else {
+            Self::Copied(data)
+        }
+    }
+}
+
+/// Device memory that is kept around between multiplications
+struct DeviceBuffer {
+    buffer: Buffer<u8>,
+    /// Size of the buffer in bytes
+    size: usize,
+}
+
+/// OpenCl state shared by consecutive multiplications on a single device
+///
+/// Context, queue and programs are created once, buffers are only reallocated when a
+/// multiplication needs more memory than the previous ones
+pub(super) struct Runtime {
+    device: Device,
+    context: Context,
+    queue: CommandQueue,
+    /// Kernel source without the element type definitions, see [sources::program_source]
+    source: &'static str,
+    /// `-D` options the source is built with
+    defines: String,
+    /// Built kernels, keyed by [Element::NAME]
+    kernels: HashMap<&'static str, Kernel>,
+    program_cache: ProgramCache,
+    buffers: [Option<DeviceBuffer>; 3],
+}
+
+impl Runtime {
+    pub fn new(device: Device, source: &'static str) -> Result<Self> {
+        let context = Context::from_device(&device)?;
+        let queue =
+            CommandQueue::create_default_with_properties(&context, CL_QUEUE_PROFILING_ENABLE, 0)?;
+
+        Ok(Self {
+            device,
+            context,
+            queue,
+            source,
+            defines: String::new(),
+            kernels: HashMap::new(),
+            program_cache: ProgramCache::default(),
+            buffers: [None, None, None],
+        })
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn set_program_cache(&mut self, program_cache: ProgramCache) {
+        self.program_cache = program_cache;
+    }
+
+    /// Builds the source with `defines` from now on, kernels that were built without them are
+    /// dropped
+    pub fn set_defines(&mut self, defines: String) {
+        if defines != self.defines {
+            self.defines = defines;
+            self.kernels.clear();
+        }
+    }
+
+    pub fn info(&self) -> Result<MultiplierInfo> {
+        let device_name = self.device.name()?;
+        let platform_name = Platform::new(self.device.platform()?).name()?;
+
+        let res = MultiplierInfo::OpenClMultiplier {
+            device_name,
+            platform_name,
+        };
+
+        Ok(res)
+    }
+
+    /// Builds the kernel for `T` unless it was already built
+    fn build<T: Element>(&mut self) -> Result<()> {
+        if !self.kernels.contains_key(T::NAME) {
+            super::check_support::<T>(&self.device)?;
+
+            let source = sources::program_source::<T>(self.source);
+            let options = format!("{CL_STD_3_0} {}", self.defines);
+            let program =
+                self.program_cache
+                    .build(&self.context, &self.device, &source, &options)?;
+            // the kernel keeps its program alive
+            let kernel = Kernel::create(&program, sources::KERNEL_NAME)?;
+
+            self.kernels.insert(T::NAME, kernel);
+        }
+
+        Ok(())
+    }
+
+    /// Makes sure buffer `index` can hold at least `size` bytes
+    fn reserve(&mut self, index: usize, size: usize) -> Result<()> {
+        if matches!(&self.buffers[index], Some(buf) if buf.size >= size) {
+            return Ok(());
+        }
+
+        let buffer = unsafe {
+            let mem = create_buffer(
+                self.context.get(),
+                BUFFER_FLAGS[index],
+                size,
+                std::ptr::null_mut(),
+            )
+            .map_err(ClError)?;
+            Buffer::new(mem)
+        };
+
+        self.buffers[index] = Some(DeviceBuffer { buffer, size });
+
+        Ok(())
+    }
+
+    fn buffer(&self, index: usize) -> &Buffer<u8> {
+        // buffers are always reserved before they are used
+        &self.buffers[index].as_ref().unwrap().buffer
+    }
+
+    /// Buffer that uses `operand` in place, `None` if it is copied instead
+    ///
+    /// The buffer must be dropped before the operand, after every command using it is done
+    fn wrap<T: Element>(&self, operand: &Operand<T>) -> Result<Option<Buffer<u8>>> {
+        let Operand::InPlace(data) = operand else {
+            return Ok(None);
+        };
+        let bytes = as_bytes(data);
+
+        // the device only reads the buffer, so the pointer is never written through
+        let buffer = unsafe {
+            let mem = create_buffer(
+                self.context.get(),
+                CL_MEM_READ_ONLY | CL_MEM_USE_HOST_PTR,
+                bytes.len(),
+                bytes.as_ptr() as *mut _,
+            )
+            .map_err(ClError)?;
+            Buffer::new(mem)
+        };
+
+        Ok(Some(buffer))
+    }
+
+    /// Copies `operand` to buffer `index` unless it is used in place
+    fn upload<T: Element>(&mut self, index: usize, operand: &Operand<T>) -> Result<Option<Event>> {
+        match operand {
+            Operand::Copied(data) => Ok(Some(self.write(index, data)?)),
+            Operand::InPlace(_) => Ok(None),
+        }
+    }
+
+    fn write<T: Element>(&mut self, index: usize, data: &[T]) -> Result<Event> {
+        let bytes = as_bytes(data);
+        self.reserve(index, bytes.len())?;
+
+        let buffer = &mut self.buffers[index].as_mut().unwrap().buffer;
+        let event = unsafe {
+            self.queue
+                .enqueue_write_buffer(buffer, CL_FALSE, 0, bytes, &[])?
+        };
+
+        Ok(event)
+    }
+
+    /// Runs the kernel computing `c = alpha * op(a) * op(b) + beta * c` for every matrix of the batch
+    ///
+    /// Work sizes are passed to the kernel launch as is, the matrices are expected to already be
+    /// padded for them
+    pub fn gemm<T: Element>(
+        &mut self,
+        params: &Gemm<T>,
+        problem: &Problem,
+        a: Operand<T>,
+        b: Operand<T>,
+        c: &mut [T],
+        work_sizes: WorkSizes,
+    ) -> Result<TimeStat> {
+        let no_time = TimeStat {
+            total_time: 0,
+            kernel_time: 0,
+        };
+
+        // OpenCl does not allow empty buffers, if the product is empty only `beta * c` is left
+        if c.is_empty() {
+            return Ok(no_time);
+        }
+        if a.data().is_empty() || b.data().is_empty() {
+            let zero = <T::Acc>::default();
+            c.iter_mut()
+                .for_each(|el| *el = super::scale(params, zero, *el));
+            return Ok(no_time);
+        }
+
+        let n = cl_arg("n", problem.n)?;
+        let m = cl_arg("m", problem.m)?;
+        let k = cl_arg("k", problem.k)?;
+        let stride_a = cl_arg("stride of a", problem.strides[0])?;
+        let stride_b = cl_arg("stride of b", problem.strides[1])?;
+        let stride_c = cl_arg("stride of c", problem.strides[2])?;
+        // kernels index the buffers with `uint`, every offset is below the length of its buffer
+        cl_arg("length of a", a.data().len())?;
+        cl_arg("length of b", b.data().len())?;
+        cl_arg("length of c", c.len())?;
+
+        self.build::<T>()?;
+
+        // dropped at the end of the launch, after the blocking read of the result
+        let in_place = [self.wrap(&a)?, self.wrap(&b)?];
+        let write_event1 = self.upload(0, &a)?;
+        let write_event2 = self.upload(1, &b)?;
+        // `c` is only read by the kernel when `beta` is not zero
+        let write_event3 = if params.beta != T::default() {
+            Some(self.write(2, c)?)
+        } else {
+            self.reserve(2, std::mem::size_of_val(c))?;
+            None
+        };
+
+        let kernel = &self.kernels[T::NAME];
+
+        unsafe {
+            kernel.set_arg(0, in_place[0].as_ref().unwrap_or_else(|| self.buffer(0)))?;
+            kernel.set_arg(1, in_place[1].as_ref().unwrap_or_else(|| self.buffer(1)))?;
+            kernel.set_arg(2, self.buffer(2))?;
+            kernel.set_arg(3, &n)?;
+            kernel.set_arg(4, &m)?;
+            kernel.set_arg(5, &k)?;
+            kernel.set_arg(6, &(params.trans_a as cl_uint))?;
+            kernel.set_arg(7, &(params.trans_b as cl_uint))?;
+            kernel.set_arg(8, &params.alpha.to_acc())?;
+            kernel.set_arg(9, &params.beta.to_acc())?;
+            kernel.set_arg(10, &stride_a)?;
+            kernel.set_arg(11, &stride_b)?;
+            kernel.set_arg(12, &stride_c)?;
+        }
+
+        // matrices of the batch are spread over the third dimension
+        let [global0, global1] = work_sizes.global;
+        let global_work_sizes = [global0, global1, problem.count];
+        let local_work_sizes = work_sizes.local.map(|[local0, local1]| [local0, local1, 1]);
+
+        let kernel_event = unsafe {
+            let local_work_sizes = match &local_work_sizes {
+                Some(sizes) => sizes.as_ptr(),
+                None => std::ptr::null(),
+            };
+            self.queue.enqueue_nd_range_kernel(
+                kernel.get(),
+                3,
+                std::ptr::null(),
+                global_work_sizes.as_ptr(),
+                local_work_sizes,
+                &[],
+            )?
+        };
+
+        let read_event = unsafe {
+            self.queue
+                .enqueue_read_buffer(self.buffer(2), CL_TRUE, 0, as_bytes_mut(c), &[])?
+        };
+
+        let read_time = super::get_profiling_info(read_event)?;
+        let kernel_time = super::get_profiling_info(kernel_event)?;
+        let mut write_time = 0;
+        for event in [write_event1, write_event2, write_event3]
+            .into_iter()
+            .flatten()
+        {
+            write_time += super::get_profiling_info(event)?;
+        }
+
+        let total_time = write_time + kernel_time + read_time;
+
+        Ok(TimeStat {
+            total_time,
+            kernel_time,
+        })
+    }
+}
+
+/// Named size as a kernel argument, an error instead of a truncated value if it does not fit
+fn cl_arg(name: &'static str, value: usize) -> Result<cl_uint> {
+    cl_uint::try_from(value).map_err(|_| MatmulError::TooLarge { name, value })
+}
@@ -0,0 +1,371 @@
+use std::any::TypeId;
+use std::fmt::Display;
+use std::num::NonZeroUsize;
+use std::thread;
+use std::time;
+
+use crate::multiplier::Gemm;
+use crate::multiplier::{Multiplier, MultiplierInfo, MultiplierStat};
+use crate::Element;
+use crate::MatmulError;
+use crate::Matrix;
+use crate::Result;
+
+use super::cpu::{self, KC, NC};
+
+/// Rows of the result computed by a single microkernel call
+const MR: usize = 4;
+
+/// Instruction set the microkernel of [SimdMultiplier] is written for
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Isa {
+    /// x86_64 with AVX-512F, 4 x 32 tiles
+    Avx512,
+    /// x86_64 with AVX2 and FMA, 4 x 16 tiles
+    Avx2,
+    /// aarch64 with NEON, 4 x 16 tiles
+    Neon,
+    /// Plain Rust, left to the compiler to vectorize
+    Scalar,
+}
+
+impl Isa {
+    /// Best instruction set the host supports
+    pub fn detect() -> Self {
+        [Self::Avx512, Self::Avx2, Self::Neon]
+            .into_iter()
+            .find(|isa| isa.is_supported())
+            .unwrap_or(Self::Scalar)
+    }
+
+    /// Whether the host can run the microkernel written for this instruction set
+    pub fn is_supported(self) -> bool {
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            Self::Avx512 => is_x86_feature_detected!("avx512f"),
+            #[cfg(target_arch = "x86_64")]
+            Self::Avx2 => is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma"),
+            #[cfg(target_arch = "aarch64")]
+            Self::Neon => std::arch::is_aarch64_feature_detected!("neon"),
+            Self::Scalar => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Columns of the result computed by a single microkernel call, zero if there is no
+    /// microkernel
+    fn width(self) -> usize {
+        match self {
+            Self::Avx512 => 32,
+            Self::Avx2 | Self::Neon => 16,
+            Self::Scalar => 0,
+        }
+    }
+}
+
+impl Display for Isa {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Avx512 => "avx512",
+            Self::Avx2 => "avx2+fma",
+            Self::Neon => "neon",
+            Self::Scalar => "scalar",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Multithreaded multiplication on the host with register blocked SIMD microkernels
+///
+/// Works like [CpuMultiplier](super::CpuMultiplier), but every `4 x width` tile of a block is
+/// computed in vector registers by a microkernel for the best instruction set of the host.
+/// Microkernels exist for `f32` accumulators, that is `f32`, `f16` and `bf16` elements, every other
+/// type uses scalar code
+pub struct SimdMultiplier {
+    isa: Isa,
+    threads: usize,
+    last_time: Option<u64>,
+}
+
+impl Default for SimdMultiplier {
+    /// One thread per available core
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl SimdMultiplier {
+    /// Multiplier that uses the best instruction set of the host and `threads` threads, or one
+    /// per available core if `None`
+    pub fn new(threads: Option<NonZeroUsize>) -> Self {
+        let threads = threads
+            .or_else(|| thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get);
+
+        Self {
+            isa: Isa::detect(),
+            threads,
+            last_time: None,
+        }
+    }
+
+    /// Use the microkernel for `isa` instead of the detected one
+    ///
+    /// May fail if the host does not support `isa`
+    pub fn with_isa(mut self, isa: Isa) -> Result<Self> {
+        if !isa.is_supported() {
+            return Err(MatmulError::UnsupportedIsa(isa));
+        }
+
+        self.isa = isa;
+        Ok(self)
+    }
+
+    /// Instruction set used for elements of type `T`
+    pub fn isa<T: Element>(&self) -> Isa {
+        if is_f32::<T::Acc>() {
+            self.isa
+        } else {
+            Isa::Scalar
+        }
+    }
+}
+
+impl<T: Element> Multiplier<T> for SimdMultiplier {
+    fn gemm(
+        &mut self,
+        params: Gemm<T>,
+        a: &Matrix<T>,
+        b: &Matrix<T>,
+        c: &mut Matrix<T>,
+    ) -> Result<()> {
+        let instant = time::Instant::now();
+        super::check_gemm_dimensions(&params, a, b, c)?;
+
+        let isa = self.isa::<T>();
+        cpu::parallel_gemm(self.threads, &params, a, b, c, |a, b, res, n, k| {
+            if isa != Isa::Scalar {
+                if let (Some(a), Some(b), Some(res)) = (as_f32(a), as_f32(b), as_f32_mut(res)) {
+                    return multiply_panel(isa, a, b, res, n, k);
+                }
+            }
+
+            cpu::multiply_panel::<T>(a, b, res, n, k)
+        });
+
+        let elapsed = instant.elapsed().as_millis() as u64;
+
+        self.last_time = Some(elapsed);
+
+        Ok(())
+    }
+
+    fn info(&self) -> Result<MultiplierInfo> {
+        Ok(MultiplierInfo::SimdMultiplier {
+            isa: self.isa::<T>(),
+        })
+    }
+
+    fn stat(&self) -> Option<MultiplierStat> {
+        self.last_time.map(|total_time| MultiplierStat {
+            total_time,
+            gpu_time: 0,
+            devices: vec![],
+        })
+    }
+}
+
+fn is_f32<T: 'static>() -> bool {
+    TypeId::of::<T>() == TypeId::of::<f32>()
+}
+
+fn as_f32<T: 'static>(data: &[T]) -> Option<&[f32]> {
+    // safe because `T` is `f32`
+    is_f32::<T>().then(|| unsafe { std::slice::from_raw_parts(data.as_ptr().cast(), data.len()) })
+}
+
+fn as_f32_mut<T: 'static>(data: &mut [T]) -> Option<&mut [f32]> {
+    // safe because `T` is `f32`
+    is_f32::<T>()
+        .then(|| unsafe { std::slice::from_raw_parts_mut(data.as_mut_ptr().cast(), data.len()) })
+}
+
+/// Same as [cpu::multiply_panel], but full tiles of every block are computed by the microkernel
+/// for `isa`
+fn multiply_panel(isa: Isa, a: &[f32], b: &[f32], res: &mut [f32], n: usize, k: usize) {
+    let rows = res.len() / n;
+    let width = isa.width();
+
+    for kb in (0..k).step_by(KC) {
+        let kc = KC.min(k - kb);
+
+        for nb in (0..n).step_by(NC) {
+            let nc = NC.min(n - nb);
+
+            let full_rows = rows - rows % MR;
+            let full_cols = nc - nc % width;
+
+            for i in (0..full_rows).step_by(MR) {
+                for j in (nb..nb + full_cols).step_by(width) {
+                    let tile_a = &a[i * k + kb..];
+                    let tile_b = &b[kb * n + j..];
+                    let tile_res = &mut res[i * n + j..];
+                    tile(isa, tile_a, tile_b, tile_res, kc, k, n);
+                }
+            }
+
+            // leftovers that do not make up a full tile, to the right of and below the tiles
+            let right = (0..full_rows).flat_map(|i| (nb + full_cols..nb + nc).map(move |j| (i, j)));
+            let below = (full_rows..rows).flat_map(|i| (nb..nb + nc).map(move |j| (i, j)));
+
+            for (i, j) in right.chain(below) {
+                let mut sum = 0.0;
+                for p in kb..kb + kc {
+                    sum += a[i * k + p] * b[p * n + j];
+                }
+                res[i * n + j] += sum;
+            }
+        }
+    }
+}
+
+/// Adds the product of `MR` rows of `a` with stride `k` and `kc` rows of `b` with stride `n` to
+/// `MR` rows of `res` with stride `n`, each row is `isa.width()` wide
+fn tile(isa: Isa, a: &[f32], b: &[f32], res: &mut [f32], kc: usize, k: usize, n: usize) {
+    let width = isa.width();
+
+    assert!(kc > 0 && width > 0);
+    assert!(a.len() >= (MR - 1) * k + kc);
+    assert!(b.len() >= (kc - 1) * n + width);
+    assert!(res.len() >= (MR - 1) * n + width);
+
+    // safe because the bounds are checked above and the host supports `isa`, see [Isa::detect]
+    unsafe {
+        match isa {
+            #[cfg(target_arch = "x86_64")]
+            Isa::Avx512 => x86::tile_avx512(a.as_ptr(), b.as_ptr(), res.as_mut_ptr(), kc, k, n),
+            #[cfg(target_arch = "x86_64")]
+            Isa::Avx2 => x86::tile_avx2(a.as_ptr(), b.as_ptr(), res.as_mut_ptr(), kc, k, n),
+            #[cfg(target_arch = "aarch64")]
+            Isa::Neon => arm::tile_neon(a.as_ptr(), b.as_ptr(), res.as_mut_ptr(), kc, k, n),
+            _ => unreachable!("no microkernel for {isa}"),
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use super::MR;
+
+    #[target_feature(enable = "avx512f")]
+    pub unsafe fn tile_avx512(
+        a: *const f32,
+        b: *const f32,
+        res: *mut f32,
+        kc: usize,
+        k: usize,
+        n: usize,
+    ) {
+        let mut acc = [[_mm512_setzero_ps(); 2]; MR];
+
+        for p in 0..kc {
+            let b0 = _mm512_loadu_ps(b.add(p * n));
+            let b1 = _mm512_loadu_ps(b.add(p * n + 16));
+
+            for (r, acc) in acc.iter_mut().enumerate() {
+                let el_a = _mm512_set1_ps(*a.add(r * k + p));
+                acc[0] = _mm512_fmadd_ps(el_a, b0, acc[0]);
+                acc[1] = _mm512_fmadd_ps(el_a, b1, acc[1]);
+            }
+        }
+
+        for (r, acc) in acc.iter().enumerate() {
+            let row = res.add(r * n);
+            _mm512_storeu_ps(row, _mm512_add_ps(_mm512_loadu_ps(row), acc[0]));
+            _mm512_storeu_ps(
+                row.add(16),
+                _mm512_add_ps(_mm512_loadu_ps(row.add(16)), acc[1]),
+            );
+        }
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn tile_avx2(
+        a: *const f32,
+        b: *const f32,
+        res: *mut f32,
+        kc: usize,
+        k: usize,
+        n: usize,
+    ) {
+        let mut acc = [[_mm256_setzero_ps(); 2]; MR];
+
+        for p in 0..kc {
+            let b0 = _mm256_loadu_ps(b.add(p * n));
+            let b1 = _mm256_loadu_ps(b.add(p * n + 8));
+
+            for (r, acc) in acc.iter_mut().enumerate() {
+                let el_a = _mm256_set1_ps(*a.add(r * k + p));
+                acc[0] = _mm256_fmadd_ps(el_a, b0, acc[0]);
+                acc[1] = _mm256_fmadd_ps(el_a, b1, acc[1]);
+            }
+        }
+
+        for (r, acc) in acc.iter().enumerate() {
+            let row = res.add(r * n);
+            _mm256_storeu_ps(row, _mm256_add_ps(_mm256_loadu_ps(row), acc[0]));
+            _mm256_storeu_ps(
+                row.add(8),
+                _mm256_add_ps(_mm256_loadu_ps(row.add(8)), acc[1]),
+            );
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arm {
+    use std::arch::aarch64::*;
+
+    use super::MR;
+
+    #[target_feature(enable = "neon")]
+    pub unsafe fn tile_neon(
+        a: *const f32,
+        b: *const f32,
+        res: *mut f32,
+        kc: usize,
+        k: usize,
+        n: usize,
+    ) {
+        let mut acc = [[vdupq_n_f32(0.0); 4]; MR];
+
+        for p in 0..kc {
+            let row_b = b.add(p * n);
+            let bs = [
+                vld1q_f32(row_b),
+                vld1q_f32(row_b.add(4)),
+                vld1q_f32(row_b.add(8)),
+                vld1q_f32(row_b.add(12)),
+            ];
+
+            for (r, acc) in acc.iter_mut().enumerate() {
+                let el_a = vdupq_n_f32(*a.add(r * k + p));
+                for (acc, &el_b) in acc.iter_mut().zip(&bs) {
+                    *acc = vfmaq_f32(*acc, el_a, el_b);
+                }
+            }
+        }
+
+        for (r, acc) in acc.iter().enumerate() {
+            let row = res.add(r * n);
+            for (c, &acc) in acc.iter().enumerate() {
+                let ptr = row.add(c * 4);
+                vst1q_f32(ptr, vaddq_f32(vld1q_f32(ptr), acc));
+            }
+        }
+    }
+}
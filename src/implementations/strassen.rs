@@ -0,0 +1,223 @@
+use std::time;
+
+use crate::accuracy::Accuracy;
+use crate::multiplier::{Gemm, Transpose};
+use crate::multiplier::{Multiplier, MultiplierInfo, MultiplierStat};
+use crate::Element;
+use crate::Matrix;
+use crate::Result;
+
+use super::BasicMultiplier;
+
+/// Strassen-Winograd multiplication, 7 multiplications and 15 additions of half sized blocks
+/// instead of 8 multiplications
+///
+/// Blocks are split until one of their dimensions is at most the crossover, then they are
+/// multiplied by the leaf multiplier. Odd dimensions are padded with zeroes on every level.
+/// Results are less precise than the ones of a classic multiplication, [Multiplier::accuracy]
+/// can measure by how much
+pub struct StrassenMultiplier<T: Element> {
+    leaf: Box<dyn Multiplier<T>>,
+    crossover: usize,
+    check_accuracy: bool,
+    stat: Option<MultiplierStat>,
+    accuracy: Option<Accuracy>,
+}
+
+impl<T: Element> StrassenMultiplier<T> {
+    /// Multiplier that hands blocks with a dimension of at most `crossover` to `leaf`
+    ///
+    /// A crossover of zero is treated as one
+    pub fn new(leaf: Box<dyn Multiplier<T>>, crossover: usize) -> Self {
+        Self {
+            leaf,
+            crossover: crossover.max(1),
+            check_accuracy: false,
+            stat: None,
+            accuracy: None,
+        }
+    }
+
+    /// Compare every result with the one of [BasicMultiplier], see [Multiplier::accuracy]
+    pub fn with_accuracy_check(mut self, check_accuracy: bool) -> Self {
+        self.check_accuracy = check_accuracy;
+        self
+    }
+
+    /// `a * b`, both operands are used as is
+    fn multiply_recursive(
+        &mut self,
+        a: &Matrix<T>,
+        b: &Matrix<T>,
+        gpu_time: &mut u64,
+    ) -> Result<Matrix<T>> {
+        let (m, k, n) = (a.rows, a.cols, b.cols);
+
+        if m.min(k).min(n) <= self.crossover {
+            let res = self.leaf.multiply(a, b)?;
+            *gpu_time += self.leaf.stat().map_or(0, |stat| stat.gpu_time);
+            return Ok(res);
+        }
+
+        let [a11, a12, a21, a22] = quadrants(a);
+        let [b11, b12, b21, b22] = quadrants(b);
+
+        let s1 = add(&a21, &a22);
+        let s2 = sub(&s1, &a11);
+        let s3 = sub(&a11, &a21);
+        let s4 = sub(&a12, &s2);
+        let t1 = sub(&b12, &b11);
+        let t2 = sub(&b22, &t1);
+        let t3 = sub(&b22, &b12);
+        let t4 = sub(&t2, &b21);
+
+        let m1 = self.multiply_recursive(&a11, &b11, gpu_time)?;
+        let m2 = self.multiply_recursive(&a12, &b21, gpu_time)?;
+        let m3 = self.multiply_recursive(&s4, &b22, gpu_time)?;
+        let m4 = self.multiply_recursive(&a22, &t4, gpu_time)?;
+        let m5 = self.multiply_recursive(&s1, &t1, gpu_time)?;
+        let m6 = self.multiply_recursive(&s2, &t2, gpu_time)?;
+        let m7 = self.multiply_recursive(&s3, &t3, gpu_time)?;
+
+        let u2 = add(&m1, &m6);
+        let u3 = add(&u2, &m7);
+        let u4 = add(&u2, &m5);
+
+        let c11 = add(&m1, &m2);
+        let c12 = add(&u4, &m3);
+        let c21 = sub(&u3, &m4);
+        let c22 = add(&u3, &m5);
+
+        Ok(join([c11, c12, c21, c22], m, n))
+    }
+}
+
+impl<T: Element> Multiplier<T> for StrassenMultiplier<T> {
+    fn gemm(
+        &mut self,
+        params: Gemm<T>,
+        a: &Matrix<T>,
+        b: &Matrix<T>,
+        c: &mut Matrix<T>,
+    ) -> Result<()> {
+        let instant = time::Instant::now();
+        super::check_gemm_dimensions(&params, a, b, c)?;
+
+        let expected = if self.check_accuracy {
+            let mut expected = Matrix::create(c.rows, c.cols, &c.data)?;
+            BasicMultiplier::default().gemm(params, a, b, &mut expected)?;
+            Some(expected)
+        } else {
+            None
+        };
+
+        let op_a = transposed(a, params.trans_a);
+        let op_b = transposed(b, params.trans_b);
+
+        let mut gpu_time = 0;
+        let product = self.multiply_recursive(&op_a, &op_b, &mut gpu_time)?;
+
+        for (el, &sum) in c.data.iter_mut().zip(product.iter()) {
+            *el = super::scale(&params, sum.to_acc(), *el);
+        }
+
+        self.stat = Some(MultiplierStat {
+            total_time: instant.elapsed().as_millis() as u64,
+            gpu_time,
+            devices: vec![],
+        });
+        self.accuracy = expected.map(|expected| Accuracy::compare(c, &expected));
+
+        Ok(())
+    }
+
+    fn info(&self) -> Result<MultiplierInfo> {
+        self.leaf.info()
+    }
+
+    fn stat(&self) -> Option<MultiplierStat> {
+        self.stat.clone()
+    }
+
+    fn accuracy(&self) -> Option<Accuracy> {
+        self.accuracy
+    }
+}
+
+/// Copy of `op(matrix)`
+fn transposed<T: Element>(matrix: &Matrix<T>, trans: Transpose) -> Matrix<T> {
+    let (rows, cols) = trans.shape(matrix);
+    let mut res = Matrix::create_empty(rows, cols);
+
+    for i in 0..rows {
+        for j in 0..cols {
+            let value = match trans {
+                Transpose::No => matrix.get(i, j),
+                Transpose::Yes => matrix.get(j, i),
+            };
+            res.set(i, j, value);
+        }
+    }
+
+    res
+}
+
+/// Top left, top right, bottom left and bottom right quarters of `matrix` padded to even
+/// dimensions
+fn quadrants<T: Element>(matrix: &Matrix<T>) -> [Matrix<T>; 4] {
+    let padded = matrix.create_zero_padded(2);
+    let (rows, cols) = (padded.rows / 2, padded.cols / 2);
+
+    [(0, 0), (0, cols), (rows, 0), (rows, cols)].map(|(row, col)| {
+        let mut res = Matrix::create_empty(rows, cols);
+        for i in 0..rows {
+            for j in 0..cols {
+                res.set(i, j, padded.get(row + i, col + j));
+            }
+        }
+        res
+    })
+}
+
+/// Reverse of [quadrants], trims the padding to `rows x cols`
+fn join<T: Element>(quadrants: [Matrix<T>; 4], rows: usize, cols: usize) -> Matrix<T> {
+    let (half_rows, half_cols) = (quadrants[0].rows, quadrants[0].cols);
+    let mut res = Matrix::create_empty(rows, cols);
+
+    for i in 0..rows {
+        for j in 0..cols {
+            let quadrant = &quadrants[(i / half_rows) * 2 + j / half_cols];
+            res.set(i, j, quadrant.get(i % half_rows, j % half_cols));
+        }
+    }
+
+    res
+}
+
+fn add<T: Element>(lhs: &Matrix<T>, rhs: &Matrix<T>) -> Matrix<T> {
+    let data = lhs
+        .iter()
+        .zip(rhs.iter())
+        .map(|(&l, &r)| l.wrapping_add(r))
+        .collect();
+
+    Matrix {
+        rows: lhs.rows,
+        cols: lhs.cols,
+        data,
+    }
+}
+
+fn sub<T: Element>(lhs: &Matrix<T>, rhs: &Matrix<T>) -> Matrix<T> {
+    let data = lhs
+        .iter()
+        .zip(rhs.iter())
+        .map(|(&l, &r)| l.wrapping_sub(r))
+        .collect();
+
+    Matrix {
+        rows: lhs.rows,
+        cols: lhs.cols,
+        data,
+    }
+}
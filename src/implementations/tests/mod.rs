@@ -29,20 +29,29 @@ macro_rules! create_test {
     };
 }
 
-use crate::args::Mode;
+use crate::mode::{DeviceSelector, Kernel, Mode};
 
 const BASIC: Mode = Mode::Basic;
+const CPU: Mode = Mode::Cpu { threads: None };
+const SIMD: Mode = Mode::Simd { threads: None };
+const STRASSEN: Mode = Mode::Strassen {
+    leaf: crate::Leaf::Cpu,
+    crossover: 1,
+    accuracy: false,
+    device: DeviceSelector::ANY,
+};
 const EASY: Mode = Mode::Easy {
-    device_type: None,
-    index: None,
+    device: DeviceSelector::ANY,
 };
 const MEDIUM: Mode = Mode::Medium {
-    device_type: None,
-    index: None,
+    device: DeviceSelector::ANY,
 };
 const HARD: Mode = Mode::Hard {
-    device_type: None,
-    index: None,
+    device: DeviceSelector::ANY,
+};
+const MULTI: Mode = Mode::Multi {
+    kernel: Kernel::Hard,
+    device: DeviceSelector::ANY,
 };
 
 const M1_1: &[f32] = &[1.0, 2.0, 3.0, 4.0];
@@ -51,7 +60,13 @@ const ANS_1: &[f32] = &[8.0, 5.0, 20.0, 13.0];
 const WRONG_ANS_1: &[f32] = &[1.0, 2.0, 3.0, 4.0];
 
 create_test!(test_basic_success_1, BASIC, true, 2, 2, 2, M1_1, M2_1, ANS_1);
+create_test!(test_cpu_success_1, CPU, true, 2, 2, 2, M1_1, M2_1, ANS_1);
+create_test!(test_strassen_success_1, STRASSEN, true, 2, 2, 2, M1_1, M2_1, ANS_1);
+create_test!(test_simd_success_1, SIMD, true, 2, 2, 2, M1_1, M2_1, ANS_1);
 create_test!(test_basic_fail_1, BASIC, false, 2, 2, 2, M1_1, M2_1, WRONG_ANS_1);
+create_test!(test_cpu_fail_1, CPU, false, 2, 2, 2, M1_1, M2_1, WRONG_ANS_1);
+create_test!(test_strassen_fail_1, STRASSEN, false, 2, 2, 2, M1_1, M2_1, WRONG_ANS_1);
+create_test!(test_simd_fail_1, SIMD, false, 2, 2, 2, M1_1, M2_1, WRONG_ANS_1);
 create_test!(test_easy_success_1, EASY, true, 2, 2, 2, M1_1, M2_1, ANS_1);
 create_test!(test_easy_fail_1, EASY, false, 2, 2, 2, M1_1, M2_1, WRONG_ANS_1);
 create_test!(test_medium_success_1, MEDIUM, true, 2, 2, 2, M1_1, M2_1, ANS_1);
@@ -65,7 +80,13 @@ const ANS_2: &[f32] = &[30.0, 36.0, 42.0, 66.0, 81.0, 96.0, 102.0, 126.0, 150.0]
 const WRONG_ANS_2: &[f32] = &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0];
 
 create_test!(test_basic_success_2, BASIC, true, 3, 3, 3, M1_2, M2_2, ANS_2);
+create_test!(test_cpu_success_2, CPU, true, 3, 3, 3, M1_2, M2_2, ANS_2);
+create_test!(test_strassen_success_2, STRASSEN, true, 3, 3, 3, M1_2, M2_2, ANS_2);
+create_test!(test_simd_success_2, SIMD, true, 3, 3, 3, M1_2, M2_2, ANS_2);
 create_test!(test_basic_fail_2, BASIC, false, 3, 3, 3, M1_2, M2_2, WRONG_ANS_2);
+create_test!(test_cpu_fail_2, CPU, false, 3, 3, 3, M1_2, M2_2, WRONG_ANS_2);
+create_test!(test_strassen_fail_2, STRASSEN, false, 3, 3, 3, M1_2, M2_2, WRONG_ANS_2);
+create_test!(test_simd_fail_2, SIMD, false, 3, 3, 3, M1_2, M2_2, WRONG_ANS_2);
 create_test!(test_easy_success_2, EASY, true, 3, 3, 3, M1_2, M2_2, ANS_2);
 create_test!(test_easy_fail_2, EASY, false, 3, 3, 3, M1_2, M2_2, WRONG_ANS_2);
 create_test!(test_medium_success_2, MEDIUM, true, 3, 3, 3, M1_2, M2_2, ANS_2);
@@ -79,7 +100,13 @@ const ANS_3: &[f32] = &[14.0, 32.0, 50.0];
 const WRONG_ANS_3: &[f32] = &[1.0, 2.0, 3.0];
 
 create_test!(test_basic_success_3, BASIC, true, 3, 3, 1, M1_3, M2_3, ANS_3);
+create_test!(test_cpu_success_3, CPU, true, 3, 3, 1, M1_3, M2_3, ANS_3);
+create_test!(test_strassen_success_3, STRASSEN, true, 3, 3, 1, M1_3, M2_3, ANS_3);
+create_test!(test_simd_success_3, SIMD, true, 3, 3, 1, M1_3, M2_3, ANS_3);
 create_test!(test_basic_fail_3, BASIC, false, 3, 3, 1, M1_3, M2_3, WRONG_ANS_3);
+create_test!(test_cpu_fail_3, CPU, false, 3, 3, 1, M1_3, M2_3, WRONG_ANS_3);
+create_test!(test_strassen_fail_3, STRASSEN, false, 3, 3, 1, M1_3, M2_3, WRONG_ANS_3);
+create_test!(test_simd_fail_3, SIMD, false, 3, 3, 1, M1_3, M2_3, WRONG_ANS_3);
 create_test!(test_easy_success_3, EASY, true, 3, 3, 1, M1_3, M2_3, ANS_3);
 create_test!(test_easy_fail_3, EASY, false, 3, 3, 1, M1_3, M2_3, WRONG_ANS_3);
 create_test!(test_medium_success_3, MEDIUM, true, 3, 3, 1, M1_3, M2_3, ANS_3);
@@ -87,8 +114,233 @@ create_test!(test_medium_fail_3, MEDIUM, false, 3, 3, 1, M1_3, M2_3, WRONG_ANS_3
 create_test!(test_hard_success_3, HARD, true, 3, 3, 1, M1_3, M2_3, ANS_3);
 create_test!(test_hard_fail_3, HARD, false, 3, 3, 1, M1_3, M2_3, WRONG_ANS_3);
 
+const M1_F64: &[f64] = &[1.0, 2.0, 3.0, 4.0];
+const M2_F64: &[f64] = &[4.0, 3.0, 2.0, 1.0];
+const ANS_F64: &[f64] = &[8.0, 5.0, 20.0, 13.0];
+
+create_test!(test_basic_success_f64, BASIC, true, 2, 2, 2, M1_F64, M2_F64, ANS_F64);
+create_test!(test_cpu_success_f64, CPU, true, 2, 2, 2, M1_F64, M2_F64, ANS_F64);
+create_test!(test_strassen_success_f64, STRASSEN, true, 2, 2, 2, M1_F64, M2_F64, ANS_F64);
+create_test!(test_simd_success_f64, SIMD, true, 2, 2, 2, M1_F64, M2_F64, ANS_F64);
+create_test!(test_easy_success_f64, EASY, true, 2, 2, 2, M1_F64, M2_F64, ANS_F64);
+create_test!(test_medium_success_f64, MEDIUM, true, 2, 2, 2, M1_F64, M2_F64, ANS_F64);
+create_test!(test_hard_success_f64, HARD, true, 2, 2, 2, M1_F64, M2_F64, ANS_F64);
+
+const M1_I32: &[i32] = &[1, 2, 3, 4, 5, 6];
+const M2_I32: &[i32] = &[1, 2, 3, 4, 5, 6];
+const ANS_I32: &[i32] = &[22, 28, 49, 64];
+
+create_test!(test_basic_success_i32, BASIC, true, 2, 3, 2, M1_I32, M2_I32, ANS_I32);
+create_test!(test_cpu_success_i32, CPU, true, 2, 3, 2, M1_I32, M2_I32, ANS_I32);
+create_test!(test_strassen_success_i32, STRASSEN, true, 2, 3, 2, M1_I32, M2_I32, ANS_I32);
+create_test!(test_simd_success_i32, SIMD, true, 2, 3, 2, M1_I32, M2_I32, ANS_I32);
+create_test!(test_easy_success_i32, EASY, true, 2, 3, 2, M1_I32, M2_I32, ANS_I32);
+create_test!(test_medium_success_i32, MEDIUM, true, 2, 3, 2, M1_I32, M2_I32, ANS_I32);
+create_test!(test_hard_success_i32, HARD, true, 2, 3, 2, M1_I32, M2_I32, ANS_I32);
+
+// Multiplies `M1_1` by `M2_1` with elements converted to `T` and checks the result against `ANS_1`
+fn test_converted<T: crate::Element>(mode: Mode) {
+    let mut multiplier = crate::multiplier::implementation::<T>(mode).unwrap();
+
+    let m1 = crate::Matrix::create(2, 2, M1_1).unwrap().convert::<T>();
+    let m2 = crate::Matrix::create(2, 2, M2_1).unwrap().convert::<T>();
+
+    let res = multiplier.multiply(&m1, &m2).unwrap().convert::<f32>();
+    let expected = crate::Matrix::create(2, 2, ANS_1).unwrap();
+
+    assert_eq!(res, expected);
+}
+
+#[test] fn test_basic_success_f16() { test_converted::<crate::f16>(BASIC) }
+#[test] fn test_cpu_success_f16() { test_converted::<crate::f16>(CPU) }
+#[test] fn test_strassen_success_f16() { test_converted::<crate::f16>(STRASSEN) }
+#[test] fn test_simd_success_f16() { test_converted::<crate::f16>(SIMD) }
+#[test] fn test_easy_success_f16() { test_converted::<crate::f16>(EASY) }
+#[test] fn test_medium_success_f16() { test_converted::<crate::f16>(MEDIUM) }
+#[test] fn test_hard_success_f16() { test_converted::<crate::f16>(HARD) }
+#[test] fn test_basic_success_bf16() { test_converted::<crate::bf16>(BASIC) }
+#[test] fn test_cpu_success_bf16() { test_converted::<crate::bf16>(CPU) }
+#[test] fn test_strassen_success_bf16() { test_converted::<crate::bf16>(STRASSEN) }
+#[test] fn test_simd_success_bf16() { test_converted::<crate::bf16>(SIMD) }
+#[test] fn test_easy_success_bf16() { test_converted::<crate::bf16>(EASY) }
+#[test] fn test_medium_success_bf16() { test_converted::<crate::bf16>(MEDIUM) }
+#[test] fn test_hard_success_bf16() { test_converted::<crate::bf16>(HARD) }
+
+#[test]
+fn test_hard_reuse() {
+    use crate::multiplier::Multiplier;
+
+    // buffers grow and shrink between calls, and a second element type needs another program
+    let mut multiplier = crate::HardMultiplier::new(Default::default(), 0).unwrap();
+
+    let cases = [
+        (2, 2, 2, M1_1, M2_1, ANS_1),
+        (3, 3, 3, M1_2, M2_2, ANS_2),
+        (3, 3, 1, M1_3, M2_3, ANS_3),
+    ];
+
+    for (n, m, k, slice1, slice2, exp) in cases {
+        let m1 = crate::Matrix::create(n, m, slice1).unwrap();
+        let m2 = crate::Matrix::create(m, k, slice2).unwrap();
+
+        let res = multiplier.multiply(&m1, &m2).unwrap();
+        assert_eq!(res, crate::Matrix::create(n, k, exp).unwrap());
+    }
+
+    let m1 = crate::Matrix::create(2, 3, M1_I32).unwrap();
+    let m2 = crate::Matrix::create(3, 2, M2_I32).unwrap();
+
+    let res = multiplier.multiply(&m1, &m2).unwrap();
+    assert_eq!(res, crate::Matrix::create(2, 2, ANS_I32).unwrap());
+}
+
+// Checks `C = alpha * op(A) * op(B) + beta * C` with both operands transposed
+fn test_gemm(mode: Mode) {
+    use crate::{Gemm, Transpose};
+
+    let mut multiplier = crate::multiplier::implementation::<f32>(mode).unwrap();
+
+    let params = Gemm {
+        alpha: 2.0,
+        beta: 0.5,
+        trans_a: Transpose::Yes,
+        trans_b: Transpose::Yes,
+    };
+
+    let a = crate::Matrix::create(2, 2, M1_1).unwrap();
+    let b = crate::Matrix::create(2, 2, M2_1).unwrap();
+    let mut c = crate::Matrix::create(2, 2, &[1.0; 4]).unwrap();
+
+    multiplier.gemm(params, &a, &b, &mut c).unwrap();
+    assert_eq!(c, crate::Matrix::create(2, 2, &[26.5, 10.5, 40.5, 16.5]).unwrap());
+
+    // non square, only the left operand is transposed
+    let params = Gemm {
+        trans_a: Transpose::Yes,
+        ..Default::default()
+    };
+
+    let a = crate::Matrix::create(3, 2, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let b = crate::Matrix::create(3, 2, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let mut c = crate::Matrix::create_empty(2, 2);
+
+    multiplier.gemm(params, &a, &b, &mut c).unwrap();
+    assert_eq!(c, crate::Matrix::create(2, 2, &[35.0, 44.0, 44.0, 56.0]).unwrap());
+}
+
+#[test] fn test_basic_gemm() { test_gemm(BASIC) }
+#[test] fn test_cpu_gemm() { test_gemm(CPU) }
+#[test] fn test_strassen_gemm() { test_gemm(STRASSEN) }
+#[test] fn test_simd_gemm() { test_gemm(SIMD) }
+#[test] fn test_easy_gemm() { test_gemm(EASY) }
+#[test] fn test_medium_gemm() { test_gemm(MEDIUM) }
+#[test] fn test_hard_gemm() { test_gemm(HARD) }
+#[test] fn test_multi_gemm() { test_gemm(MULTI) }
+
+fn test_batch(mode: Mode) {
+    use crate::StridedBatch;
+
+    let mut multiplier = crate::multiplier::implementation::<f32>(mode).unwrap();
+
+    let a = [crate::Matrix::create(2, 2, M1_1).unwrap(), crate::Matrix::create(2, 2, M2_1).unwrap()];
+    let b = [crate::Matrix::create(2, 2, M2_1).unwrap(), crate::Matrix::create(2, 2, M1_1).unwrap()];
+
+    let res = multiplier.multiply_batch(&a, &b).unwrap();
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[0], crate::Matrix::create(2, 2, ANS_1).unwrap());
+    assert_eq!(res[1], crate::Matrix::create(2, 2, &[13.0, 20.0, 5.0, 8.0]).unwrap());
+
+    // shapes differ, matrices are multiplied one by one
+    let a = [crate::Matrix::create(2, 2, M1_1).unwrap(), crate::Matrix::create(3, 3, M1_2).unwrap()];
+    let b = [crate::Matrix::create(2, 2, M2_1).unwrap(), crate::Matrix::create(3, 3, M2_2).unwrap()];
+
+    let res = multiplier.multiply_batch(&a, &b).unwrap();
+    assert_eq!(res[0], crate::Matrix::create(2, 2, ANS_1).unwrap());
+    assert_eq!(res[1], crate::Matrix::create(3, 3, ANS_2).unwrap());
+
+    // every 3x3 matrix of `a` is followed by an unused element, `b` is the same 3x1 vector
+    let data_a = [M1_2, &[0.0], M1_2, &[0.0], M1_2].concat();
+    let batch_a = StridedBatch { stride: 10, ..StridedBatch::new(&data_a, 3, 3, 3) };
+    let batch_b = StridedBatch { stride: 0, ..StridedBatch::new(M2_3, 3, 1, 3) };
+
+    let res = multiplier.multiply_strided_batch(&batch_a, &batch_b).unwrap();
+    assert_eq!(crate::Matrix::create(3, 3, &res).unwrap(), crate::Matrix::create(3, 3, &[ANS_3; 3].concat()).unwrap());
+
+    assert!(multiplier.multiply_batch(&a, &b[..1]).is_err());
+    let short = StridedBatch::new(M2_3, 3, 1, 2);
+    assert!(multiplier.multiply_strided_batch(&batch_a, &short).is_err());
+}
+
+#[test] fn test_basic_batch() { test_batch(BASIC) }
+#[test] fn test_cpu_batch() { test_batch(CPU) }
+#[test] fn test_strassen_batch() { test_batch(STRASSEN) }
+#[test] fn test_simd_batch() { test_batch(SIMD) }
+#[test] fn test_easy_batch() { test_batch(EASY) }
+#[test] fn test_medium_batch() { test_batch(MEDIUM) }
+#[test] fn test_hard_batch() { test_batch(HARD) }
+#[test] fn test_multi_batch() { test_batch(MULTI) }
+
+#[test]
+fn test_basic_dimension_mismatch() {
+    let mut multiplier = crate::multiplier::implementation(BASIC).unwrap();
+
+    let m1 = crate::Matrix::create(2, 3, &[0.0; 6]).unwrap();
+    let m2 = crate::Matrix::create(2, 2, &[0.0; 4]).unwrap();
+
+    let err = multiplier.multiply(&m1, &m2).unwrap_err();
+
+    assert!(matches!(
+        err,
+        crate::MatmulError::DimensionMismatch { left: (2, 3), right: (2, 2) }
+    ));
+}
+
+#[test]
+fn test_integer_overflow() {
+    // integers wrap around like in the OpenCl kernels, also in debug builds
+    let a = crate::Matrix::create(2, 2, &[i32::MAX, 1, 1, i32::MAX]).unwrap();
+    let b = crate::Matrix::create(2, 2, &[2, 0, 1, 1]).unwrap();
+    let expected = crate::Matrix::create(2, 2, &[-1, 1, i32::MIN + 1, i32::MAX]).unwrap();
+
+    for mode in [BASIC, CPU, SIMD, STRASSEN] {
+        let mut multiplier = crate::multiplier::implementation::<i32>(mode.clone()).unwrap();
+        assert_eq!(multiplier.multiply(&a, &b).unwrap(), expected, "{mode:?}");
+    }
+}
+
 use rand::prelude::*;
 
+// Checks that `actual` is within the rounding error of a product of `a` by `b` of `expected`
+fn assert_product_eq<T: crate::Element>(actual: &crate::Matrix<T>, expected: &crate::Matrix<T>, a: &crate::Matrix<T>, b: &crate::Matrix<T>) {
+    let diff = actual.diff(expected, a.product_tolerance(b), 0.0);
+    assert!(diff.is_empty(), "{diff}");
+}
+
+// Seeded `gemm` of `m x k` by `k x n` with transposed operands, and its result by the basic multiplier
+struct GemmCase {
+    params: crate::Gemm<f32>,
+    a: crate::Matrix<f32>,
+    b: crate::Matrix<f32>,
+    c: crate::Matrix<f32>,
+    expected: crate::Matrix<f32>,
+}
+
+fn gemm_case(m: usize, n: usize, k: usize, alpha: f32, beta: f32, seed: u64) -> GemmCase {
+    use crate::{Distribution, Multiplier, Transpose};
+
+    let mut generator = crate::Generator::new(seed);
+    let a = generator.matrix(k, m, Distribution::Uniform { low: 0.0, high: 1.0 });
+    let b = generator.matrix(n, k, Distribution::Uniform { low: 0.0, high: 1.0 });
+    let c = generator.matrix(m, n, Distribution::Uniform { low: 0.0, high: 1.0 });
+
+    let params = crate::Gemm { alpha, beta, trans_a: Transpose::Yes, trans_b: Transpose::Yes };
+
+    let mut expected = crate::Matrix::create(c.rows, c.cols, &c.data).unwrap();
+    crate::BasicMultiplier::default().gemm(params, &a, &b, &mut expected).unwrap();
+
+    GemmCase { params, a, b, c, expected }
+}
+
 struct Case {
     m1: crate::Matrix,
     m2: crate::Matrix,
@@ -103,21 +355,21 @@ impl Case {
         let actual = multiplier.multiply(&self.m1, &self.m2).unwrap();
         let expected = basic_multiplier.multiply(&self.m1, &self.m2).unwrap();
 
-        assert_eq!(actual, expected);
+        assert_product_eq(&actual, &expected, &self.m1, &self.m2);
     }
 }
 
 fn generate_case() -> Case {
+    use crate::Distribution;
+
     let mut rng = rand::thread_rng();
     let n = rng.gen::<usize>() % 100;
     let m = rng.gen::<usize>() % 100;
     let k = rng.gen::<usize>() % 100;
 
-    let mut m1 = crate::Matrix::create_empty(n, m);
-    let mut m2 = crate::Matrix::create_empty(m, k);
-
-    m1.iter_mut().for_each(|el| *el = rng.gen::<f32>() % 1000.0);
-    m2.iter_mut().for_each(|el| *el = rng.gen::<f32>() % 1000.0);
+    let mut generator = crate::Generator::new(rng.gen());
+    let m1 = generator.matrix(n, m, Distribution::Uniform { low: 0.0, high: 1.0 });
+    let m2 = generator.matrix(m, k, Distribution::Uniform { low: 0.0, high: 1.0 });
 
     Case { m1, m2 }
 }
@@ -126,9 +378,308 @@ fn generate_case() -> Case {
 fn random_tests() {
     for _ in 0..5 {
         let case = generate_case();
+        case.test_case(CPU);
+        case.test_case(SIMD);
         // test gpu implementations
         case.test_case(EASY);
         case.test_case(MEDIUM);
         case.test_case(HARD);
     }
 }
+
+#[test]
+fn test_cpu_blocked() {
+    use crate::{CpuMultiplier, Multiplier};
+
+    // larger than a block in every dimension, with transposed operands
+    let GemmCase { params, a, b, c, expected } = gemm_case(270, 301, 300, 0.5, 2.0, 9);
+
+    for threads in [1, 3, 64] {
+        let mut multiplier = CpuMultiplier::new(std::num::NonZeroUsize::new(threads));
+        let mut actual = crate::Matrix::create(c.rows, c.cols, &c.data).unwrap();
+        multiplier.gemm(params, &a, &b, &mut actual).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+}
+
+#[test]
+fn test_simd_isa() {
+    use crate::{Gemm, Isa, Multiplier, SimdMultiplier};
+
+    let mut rng = rand::thread_rng();
+
+    // full tiles for every microkernel plus leftovers to the right and below
+    let mut a = crate::Matrix::create_empty(135, 300);
+    let mut b = crate::Matrix::create_empty(300, 301);
+    a.iter_mut().for_each(|el| *el = rng.gen::<f32>());
+    b.iter_mut().for_each(|el| *el = rng.gen::<f32>());
+
+    let expected = crate::BasicMultiplier::default().multiply(&a, &b).unwrap();
+
+    for isa in [Isa::Avx512, Isa::Avx2, Isa::Neon, Isa::Scalar] {
+        let Ok(mut multiplier) = SimdMultiplier::new(None).with_isa(isa) else {
+            assert!(!isa.is_supported());
+            continue;
+        };
+
+        assert!(matches!(Multiplier::<f32>::info(&multiplier).unwrap(), crate::MultiplierInfo::SimdMultiplier { isa: actual } if actual == isa));
+        assert_product_eq(&multiplier.multiply(&a, &b).unwrap(), &expected, &a, &b);
+
+        let mut c = crate::Matrix::create_empty(135, 301);
+        multiplier.gemm(Gemm { beta: 1.0, ..Default::default() }, &a, &b, &mut c).unwrap();
+        assert_product_eq(&c, &expected, &a, &b);
+    }
+
+    // only `f32` accumulators have microkernels
+    assert_eq!(SimdMultiplier::new(None).isa::<i32>(), Isa::Scalar);
+}
+
+#[test]
+fn test_strassen_accuracy() {
+    use crate::{Multiplier, StrassenMultiplier};
+
+    // odd dimensions are padded on every level
+    let GemmCase { params, a, b, mut c, expected } = gemm_case(67, 70, 45, 2.0, 0.5, 11);
+
+    let leaf = Box::new(crate::BasicMultiplier::default());
+    let mut multiplier = StrassenMultiplier::new(leaf, 8).with_accuracy_check(true);
+    multiplier.gemm(params, &a, &b, &mut c).unwrap();
+
+    assert_product_eq(&c, &expected, &a, &b);
+
+    let accuracy = multiplier.accuracy().unwrap();
+    assert!(accuracy.max_abs_error < 0.01);
+    assert!(accuracy.rel_error < 1e-5);
+
+    // no accuracy unless asked for
+    let leaf = Box::new(crate::BasicMultiplier::default());
+    let mut multiplier = StrassenMultiplier::new(leaf, 8);
+    multiplier.multiply(&a, &a).unwrap_err();
+    multiplier.multiply(&b, &a).unwrap();
+    assert!(multiplier.accuracy().is_none());
+}
+
+#[test]
+fn test_multi_split() {
+    use crate::{BasicMultiplier, CpuMultiplier, MultiDeviceMultiplier, Multiplier};
+
+    // more rows than parts times a tile, and a part that can not get a tile of its own
+    let GemmCase { params, a, b, c, expected } = gemm_case(70, 33, 40, 2.0, 0.5, 20);
+
+    let parts: Vec<Box<dyn Multiplier<f32> + Send>> = vec![
+        Box::new(BasicMultiplier::default()),
+        Box::new(CpuMultiplier::new(std::num::NonZeroUsize::new(2))),
+        Box::new(BasicMultiplier::default()),
+        Box::new(BasicMultiplier::default()),
+        Box::new(BasicMultiplier::default()),
+        Box::new(BasicMultiplier::default()),
+    ];
+    let mut multiplier = MultiDeviceMultiplier::new(parts).unwrap();
+
+    // the second run is split by the throughput measured in the first one
+    for _ in 0..2 {
+        let mut actual = crate::Matrix::create(c.rows, c.cols, &c.data).unwrap();
+        multiplier.gemm(params, &a, &b, &mut actual).unwrap();
+        assert_eq!(actual, expected);
+
+        let stat = multiplier.stat().unwrap();
+        assert_eq!(stat.devices.iter().map(|device| device.rows).sum::<usize>(), 70);
+        assert!(stat.devices.iter().all(|device| device.rows > 0));
+    }
+
+    assert!(matches!(multiplier.info().unwrap(), crate::MultiplierInfo::MultiDeviceMultiplier { devices } if devices.len() == 6));
+}
+
+#[test]
+fn test_device_selector_display() {
+    assert_eq!(DeviceSelector::ANY.to_string(), "any device");
+    assert_eq!(DeviceSelector::new(crate::DeviceType::Gpu, 1).to_string(), "type Gpu, index 1");
+
+    let selector = DeviceSelector { device: Some("0:2".parse().unwrap()), platform: Some(regex::Regex::new("CUDA").unwrap()), ..DeviceSelector::ANY };
+    assert_eq!(selector.to_string(), "device 0:2, platform matching `CUDA`");
+    assert_eq!(crate::MatmulError::NoDevice(selector).to_string(), "no OpenCl device matches device 0:2, platform matching `CUDA`");
+}
+
+#[test]
+fn test_basic_accuracy() {
+    use crate::{Accuracy, Element, ElementError};
+
+    let actual = crate::Matrix::create(2, 2, &[1.0f32, 2.0, 3.0, 4.5]).unwrap();
+    let expected = crate::Matrix::create(2, 2, &[1.0f32, 2.0, 3.0, 4.0]).unwrap();
+
+    let accuracy = Accuracy::compare(&actual, &expected);
+    assert_eq!(accuracy.max_abs_error, 0.5);
+    assert_eq!(accuracy.max_rel_error, 0.125);
+    // floats in [4, 8) are 2^-21 apart
+    assert_eq!(accuracy.max_ulps, 1 << 20);
+    assert_eq!(accuracy.worst, Some(ElementError { row: 1, col: 1, actual: 4.5, expected: 4.0 }));
+
+    // an element that should be zero is infinitely wrong relative to it
+    let actual = crate::Matrix::create(1, 2, &[0.0f64, 1e-300]).unwrap();
+    let expected = crate::Matrix::create(1, 2, &[-0.0f64, 0.0]).unwrap();
+    let accuracy = Accuracy::compare(&actual, &expected);
+    assert_eq!(accuracy.max_rel_error, f64::INFINITY);
+    assert_eq!(accuracy.worst.unwrap().col, 1);
+
+    assert_eq!(0.0f32.ulps(-0.0), 0);
+    assert_eq!(f32::from_bits(1).ulps(-f32::from_bits(1)), 2);
+    assert_eq!(f32::NAN.ulps(1.0), u64::MAX);
+    assert_eq!(crate::f16::ONE.ulps(crate::f16::ONE + crate::f16::EPSILON), 1);
+    assert_eq!(crate::bf16::NEG_ONE.ulps(crate::bf16::ONE), 2 * 0x3F80);
+    assert_eq!(i64::MIN.ulps(i64::MAX), u64::MAX);
+}
+
+#[test]
+fn test_cpu_verify() {
+    use crate::{verify, Distribution, Generator, Reference, Tolerance};
+
+    let mut generator = Generator::new(3);
+    let a = generator.matrix::<f32>(37, 300, Distribution::Uniform { low: -1.0, high: 1.0 });
+    let b = generator.matrix::<f32>(300, 41, Distribution::Uniform { low: -1.0, high: 1.0 });
+
+    let mut multiplier = crate::multiplier::implementation::<f32>(CPU).unwrap();
+    for reference in [Reference::Basic, Reference::F64] {
+        let res = verify(multiplier.as_mut(), &a, &b, reference, Tolerance::for_product::<f32>(300)).unwrap();
+        assert!(res.passed(), "{:?}", res.accuracy);
+    }
+
+    // rounding to f16 is far beyond the bounds for f32
+    let res = verify(multiplier.as_mut(), &a, &b, Reference::F64, Tolerance::for_product::<f32>(300)).unwrap();
+    let tolerance = Tolerance { max_ulps: Some(0), ..Tolerance::for_product::<f32>(1) };
+    let mut half = crate::multiplier::implementation::<crate::f16>(CPU).unwrap();
+    let half_res = verify(half.as_mut(), &a.convert(), &b.convert(), Reference::F64, tolerance).unwrap();
+    assert!(!half_res.passed());
+    assert!(half_res.accuracy.rel_error > res.accuracy.rel_error);
+    let names = tolerance.violations(&half_res.accuracy).into_iter().map(|(name, _, _)| name).collect::<Vec<_>>();
+    assert_eq!(names, ["max ulp distance", "relative error"]);
+
+    // integer products are exact
+    let a = generator.matrix::<i32>(20, 30, Distribution::Integers { low: -100, high: 100 });
+    let b = generator.matrix::<i32>(30, 10, Distribution::Integers { low: -100, high: 100 });
+    let mut multiplier = crate::multiplier::implementation::<i32>(CPU).unwrap();
+    let res = verify(multiplier.as_mut(), &a, &b, Reference::F64, Tolerance::for_product::<i32>(30)).unwrap();
+    assert!(res.passed());
+    assert_eq!(res.accuracy.max_abs_error, 0.0);
+
+    verify(multiplier.as_mut(), &a, &a, Reference::Basic, Tolerance::default()).unwrap_err();
+}
+
+#[test]
+fn test_basic_approx_eq() {
+    let a = crate::Matrix::create(2, 2, &[1.0f32, 1e-6, 100.0, -3.0]).unwrap();
+    let b = crate::Matrix::create(2, 2, &[1.0f32, 0.0, 100.001, -3.0]).unwrap();
+
+    assert_ne!(a, b);
+    assert!(a.approx_eq(&b, 1e-5, 1e-5));
+    // the absolute tolerance covers zero, the relative one large values
+    assert!(!a.approx_eq(&b, 1e-5, 0.0));
+    assert!(!a.approx_eq(&b, 0.0, 1e-5));
+    assert!(!a.approx_eq(&crate::Matrix::create(1, 4, &a.data).unwrap(), 1.0, 1.0));
+
+    // 1e-6 is hundreds of millions of floats away from zero, 100.001 is 131 above 100
+    assert!(!a.ulps_eq(&b, 1 << 29));
+    let c = crate::Matrix::create(2, 2, &[1.0f32, 1e-6, 100.001, -3.0]).unwrap();
+    assert!(a.ulps_eq(&c, 131));
+    assert!(!a.ulps_eq(&c, 130));
+
+    let diff = a.diff(&b, 1e-7, 1e-7);
+    assert_eq!(diff.len, 4);
+    assert_eq!(diff.mismatches.iter().map(|el| (el.row, el.col)).collect::<Vec<_>>(), [(0, 1), (1, 0)]);
+    assert_eq!(diff.accuracy.worst.unwrap().row, 1);
+    assert!(diff.to_string().starts_with("2 of 4 elements differ"), "{diff}");
+    assert!(a.diff(&b, 1e-5, 1e-5).is_empty());
+    let diff = a.diff(&crate::Matrix::create(1, 4, &a.data).unwrap(), 1.0, 1.0);
+    assert_eq!(diff.shapes, Some(((2, 2), (1, 4))));
+    assert!(!diff.is_empty());
+    assert_eq!(diff.to_string(), "shapes differ, 2x2 instead of 1x4");
+
+    // integers are exact
+    let ints = crate::Matrix::create(1, 2, &[1, 2]).unwrap();
+    assert_eq!(ints.product_tolerance(&ints), 0.0);
+    assert!(crate::Matrix::create(2, 1, &[0.5f32, -0.5]).unwrap().product_tolerance(&ints.convert()) > 0.0);
+}
+
+#[test]
+fn test_write_atomically() {
+    let dir = std::env::temp_dir().join(format!("matmul-atomic-{}", std::process::id()));
+    let path = dir.join("program.bin");
+
+    // threads of the same process writing the same file never mix their contents
+    let contents = (0..8u8).map(|i| vec![i; 1 << 16]).collect::<Vec<_>>();
+    std::thread::scope(|scope| {
+        for content in &contents {
+            scope.spawn(|| crate::cache::write_atomically(&path, content).unwrap());
+        }
+    });
+
+    assert!(contents.contains(&std::fs::read(&path).unwrap()));
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_tuning_database() {
+    use crate::{KernelConfig, TuningDatabase};
+
+    let candidates = KernelConfig::candidates();
+    assert!(candidates.contains(&KernelConfig::default()));
+    assert!(candidates.iter().all(|config| config.is_valid() && config.tile % config.elem_per_thread == 0));
+    assert!(!KernelConfig { tile: 16, elem_per_thread: 2, width: 4 }.is_valid());
+    assert!(!KernelConfig { tile: 0, elem_per_thread: 1, width: 1 }.is_valid());
+    assert_eq!(KernelConfig::default().defines(), "-D TILE=16 -D ELEM_PER_THREAD=2 -D WIDTH=2");
+
+    let path = std::env::temp_dir().join(format!("matmul-tuning-{}.txt", std::process::id()));
+    let database = TuningDatabase::with_path(&path);
+    let config = KernelConfig { tile: 32, elem_per_thread: 4, width: 4 };
+
+    assert_eq!(database.get("gpu", "1.0").unwrap(), None);
+    database.set("gpu", "1.0", KernelConfig::default()).unwrap();
+    database.set("other\tgpu", "2.0", config).unwrap();
+    database.set("gpu", "1.0", config).unwrap();
+
+    // the driver version is part of the key
+    assert_eq!(database.get("gpu", "1.0").unwrap(), Some(config));
+    assert_eq!(database.get("gpu", "1.1").unwrap(), None);
+    assert_eq!(database.get("other\tgpu", "2.0").unwrap(), Some(config));
+    assert_eq!(database.entries().unwrap().len(), 2);
+
+    // broken lines are skipped
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, content + "16 3 1\t1.0\tbad\n16 2\t1.0\tshort\n0 1 1\t1.0\tzero\n").unwrap();
+    assert_eq!(database.entries().unwrap().len(), 2);
+    assert_eq!(database.get("zero", "1.0").unwrap(), None);
+
+    // invalid configs are never stored
+    assert!(database.set("zero", "1.0", KernelConfig { tile: 0, elem_per_thread: 1, width: 1 }).is_err());
+    assert_eq!(database.entries().unwrap().len(), 2);
+
+    std::fs::remove_file(&path).unwrap();
+
+    let disabled = TuningDatabase::disabled();
+    disabled.set("gpu", "1.0", config).unwrap();
+    assert_eq!(disabled.get("gpu", "1.0").unwrap(), None);
+}
+
+#[test]
+fn test_hard_config() {
+    use crate::{HardMultiplier, KernelConfig, Multiplier};
+
+    let GemmCase { params, a, b, c, expected } = gemm_case(70, 33, 50, 2.0, 0.5, 25);
+
+    let device = super::devices::select(&DeviceSelector::ANY).unwrap();
+    for config in KernelConfig::candidates().into_iter().filter(|&config| crate::tuning::fits(config, &device, crate::tuning::MAX_ACC_SIZE)) {
+        let mut multiplier = HardMultiplier::from_tuning_database(&DeviceSelector::ANY, &crate::TuningDatabase::disabled()).unwrap().with_config(config).unwrap();
+        let mut actual = crate::Matrix::create(c.rows, c.cols, &c.data).unwrap();
+        multiplier.gemm(params, &a, &b, &mut actual).unwrap();
+
+        let diff = actual.diff(&expected, a.product_tolerance(&b), 0.0);
+        assert!(diff.is_empty(), "{config:?}: {diff}");
+        assert_eq!(multiplier.config(), config);
+    }
+
+    let invalid = KernelConfig { width: 3, ..KernelConfig::default() };
+    let multiplier = HardMultiplier::from_tuning_database(&DeviceSelector::ANY, &crate::TuningDatabase::disabled()).unwrap();
+    assert!(matches!(multiplier.with_config(invalid), Err(crate::MatmulError::InvalidConfig { config, .. }) if config == invalid));
+}
@@ -0,0 +1,129 @@
+use std::num::NonZeroUsize;
+use std::time;
+
+use crate::mode::DeviceSelector;
+use crate::multiplier::{Multiplier, Options};
+use crate::random::{Distribution, Generator};
+use crate::sources::KernelConfig;
+use crate::tuning::{self, TuningDatabase};
+use crate::Element;
+use crate::ElementType;
+use crate::Result;
+
+use super::{CpuMultiplier, HardMultiplier};
+
+/// Timings of every [KernelConfig] that fits a device, see [tune]
+#[derive(Clone, Debug)]
+pub struct Tuning {
+    /// Name of the device
+    pub device: String,
+    pub driver_version: String,
+    /// Results of the [candidates](KernelConfig::candidates) that fit the device, in their order
+    pub results: Vec<TuningResult>,
+}
+
+/// Timing of a single [KernelConfig]
+#[derive(Clone, Debug)]
+pub struct TuningResult {
+    pub config: KernelConfig,
+    /// Median time of a multiplication in milliseconds, or why the config does not work
+    pub time: std::result::Result<f64, String>,
+}
+
+impl Tuning {
+    /// Fastest config that gave correct results, `None` if none of them did
+    pub fn best(&self) -> Option<&TuningResult> {
+        self.results
+            .iter()
+            .filter(|res| res.time.is_ok())
+            .min_by(|a, b| a.time.as_ref().unwrap().total_cmp(b.time.as_ref().unwrap()))
+    }
+
+    /// Stores the fastest config in `tuning_database`, so that [HardMultiplier] uses it on the
+    /// device from now on
+    ///
+    /// Returns the stored config, `None` if no config worked and nothing was stored
+    pub fn save(&self, tuning_database: &TuningDatabase) -> Result<Option<KernelConfig>> {
+        let Some(best) = self.best() else {
+            return Ok(None);
+        };
+
+        tuning_database.set(&self.device, &self.driver_version, best.config)?;
+
+        Ok(Some(best.config))
+    }
+}
+
+/// Times [HardMultiplier] with every [KernelConfig] that fits the device picked by `selector`
+///
+/// Each config multiplies two random `size x size` matrices of `T` once to build the kernel and
+/// check the result, then `repetitions` more times. Configs whose kernel fails to build or run, or
+/// gives wrong results, are kept with the error instead of a time. Programs are cached as
+/// `options` say, its tuning database is not used
+///
+/// Configs are stored per device, the best one for `T` is used for every element type
+pub fn tune<T: Element>(
+    selector: &DeviceSelector,
+    size: usize,
+    repetitions: NonZeroUsize,
+    options: &Options,
+) -> Result<Tuning> {
+    let device = super::devices::select(selector)?;
+
+    // same operands for every run, so that the times are comparable
+    let mut generator = Generator::new(0);
+    let distribution = match T::TYPE {
+        ElementType::I32 | ElementType::I64 => Distribution::Integers { low: -8, high: 8 },
+        _ => Distribution::Uniform {
+            low: -1.0,
+            high: 1.0,
+        },
+    };
+    let a = generator.matrix::<T>(size, size, distribution);
+    let b = generator.matrix::<T>(size, size, distribution);
+
+    let expected = CpuMultiplier::new(None).multiply(&a, &b)?;
+    let tolerance = a.product_tolerance(&b);
+
+    let measure = |config: KernelConfig| -> std::result::Result<f64, String> {
+        let mut multiplier = HardMultiplier::on_device(device, config)
+            .map_err(|e| e.to_string())?
+            .with_program_cache(options.program_cache.clone());
+
+        let res = multiplier.multiply(&a, &b).map_err(|e| e.to_string())?;
+        let diff = res.diff(&expected, tolerance, 0.0);
+        if !diff.is_empty() {
+            return Err(format!(
+                "{} of {} elements are wrong",
+                diff.mismatches.len(),
+                diff.len
+            ));
+        }
+
+        let mut times = vec![];
+        for _ in 0..repetitions.get() {
+            let instant = time::Instant::now();
+            multiplier.multiply(&a, &b).map_err(|e| e.to_string())?;
+            times.push(instant.elapsed().as_secs_f64() * 1e3);
+        }
+        times.sort_by(f64::total_cmp);
+
+        Ok(times[times.len() / 2])
+    };
+
+    let acc_size = std::mem::size_of::<T::Acc>();
+    let results = KernelConfig::candidates()
+        .into_iter()
+        .filter(|&config| tuning::fits(config, &device, acc_size))
+        .map(|config| TuningResult {
+            config,
+            time: measure(config),
+        })
+        .collect();
+
+    Ok(Tuning {
+        device: device.name()?,
+        driver_version: device.driver_version()?,
+        results,
+    })
+}
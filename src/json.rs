@@ -0,0 +1,52 @@
+/// JSON object of `fields`, whose values are JSON already, closed at `indent` levels
+pub fn object(fields: &[(&str, String)], indent: usize) -> String {
+    let pad = "  ".repeat(indent);
+    let fields = fields
+        .iter()
+        .map(|(key, value)| format!("{pad}  \"{key}\": {value}"))
+        .collect::<Vec<_>>();
+
+    format!("{{\n{}\n{pad}}}", fields.join(",\n"))
+}
+
+/// JSON array of `values`, which are JSON already, closed at `indent` levels
+pub fn array(values: &[String], indent: usize) -> String {
+    if values.is_empty() {
+        return "[]".to_string();
+    }
+
+    let pad = "  ".repeat(indent);
+    let values = values
+        .iter()
+        .map(|value| format!("{pad}  {value}"))
+        .collect::<Vec<_>>();
+
+    format!("[\n{}\n{pad}]", values.join(",\n"))
+}
+
+/// JSON string literal of `value`
+pub fn string(value: &str) -> String {
+    let mut res = String::from('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            c if c.is_control() => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+
+    res.push('"');
+    res
+}
+
+/// JSON number of `value`, `null` if it is infinite or NaN, which JSON has no numbers for
+pub fn number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
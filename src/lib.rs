@@ -0,0 +1,61 @@
+//! Matrix multiplication on the host and on OpenCl devices
+//!
+//! The crate exposes a [Matrix] type, the [Multiplier] trait and a handful of multipliers that
+//! implement it. [implementation] builds a multiplier from a [Mode], which is what the
+//! `rust-matmul` binary uses, but every multiplier can also be constructed directly:
+//!
+//! ```no_run
+//! use matmul::{DeviceType, HardMultiplier, Matrix, Multiplier};
+//!
+//! let m1 = Matrix::create(2, 2, &[1.0, 2.0, 3.0, 4.0]).unwrap();
+//! let m2 = Matrix::create(2, 2, &[4.0, 3.0, 2.0, 1.0]).unwrap();
+//!
+//! let mut multiplier = HardMultiplier::new(DeviceType::Gpu, 0).unwrap();
+//! let res = multiplier.multiply(&m1, &m2).unwrap();
+//! ```
+
+mod accuracy;
+mod cache;
+mod element;
+mod error;
+mod implementations;
+mod matrix;
+mod mode;
+mod multiplier;
+mod parse;
+mod random;
+mod sources;
+mod sparse;
+mod storage;
+mod tuning;
+
+pub use accuracy::{verify, Reference, Tolerance, Verification};
+pub use accuracy::{Accuracy, ElementError, MatrixDiff};
+pub use cache::ProgramCache;
+pub use element::{Element, ElementType};
+pub use error::MatmulError;
+pub use implementations::MultiDeviceMultiplier;
+pub use implementations::{platforms, DeviceInfo, PlatformInfo};
+pub use implementations::{tune, Tuning, TuningResult};
+pub use implementations::{BasicMultiplier, CpuMultiplier, Isa, SimdMultiplier};
+pub use implementations::{EasyMultiplier, HardMultiplier, MediumMultiplier, StrassenMultiplier};
+pub use matrix::Matrix;
+pub use mode::{DeviceMatch, DeviceSelector, DeviceType, Kernel, Leaf, Mode};
+pub use multiplier::DeviceStat;
+pub use multiplier::{implementation, implementation_with_options, Options};
+pub use multiplier::{Gemm, Multiplier, MultiplierInfo, MultiplierStat, StridedBatch, Transpose};
+pub use parse::write_operands;
+pub use parse::{parse_file, parse_file_mapped, parse_matrix_file, parse_matrix_file_mapped};
+pub use parse::{read_binary, read_npy, read_npz, write_binary, write_npy, write_npz};
+pub use parse::{read_csv, write_csv, CsvOptions};
+pub use parse::{read_mtx, read_mtx_sparse, write_mtx, write_mtx_sparse};
+pub use parse::{write_file, write_file_as, FileFormat};
+pub use random::{Distribution, Generator};
+pub use sources::KernelConfig;
+pub use sparse::SparseMatrix;
+pub use storage::Storage;
+pub use tuning::{TuningDatabase, TuningEntry};
+
+pub use half::{bf16, f16};
+
+pub type Result<T> = std::result::Result<T, MatmulError>;
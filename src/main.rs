@@ -1,27 +1,162 @@
 mod args;
-mod implementations;
-mod matrix;
-mod multiplier;
-mod parse;
-mod sources;
+mod bench;
+mod devices;
+mod generate;
+mod json;
+mod tune;
+mod verify;
 
-use std::fs;
 use std::path::Path;
 
 use clap::Parser;
 
-use args::Args;
-use matrix::Matrix;
-use multiplier::implementation;
-use multiplier::{MultiplierInfo, MultiplierStat};
+use matmul::implementation_with_options;
+use matmul::{bf16, f16};
+use matmul::{CsvOptions, Matrix, Result};
+use matmul::{Element, ElementType};
+use matmul::{MultiplierInfo, MultiplierStat};
+use matmul::{Options, ProgramCache, TuningDatabase};
 
-type Error = dyn std::error::Error;
-type Result<T> = std::result::Result<T, Box<Error>>;
+use args::{Args, Command};
 
 fn main() {
     let cli = Args::parse();
 
-    let (m1, m2) = match parse::parse_file(Path::new(&cli.input)) {
+    if let Command::Devices { json } = cli.command {
+        devices::print(json);
+        return;
+    }
+
+    if let Command::Bench(args) = &cli.command {
+        let Some(options) = options(&cli) else {
+            return;
+        };
+
+        match cli.dtype {
+            ElementType::F32 => bench::run::<f32>(args, &options),
+            ElementType::F64 => bench::run::<f64>(args, &options),
+            ElementType::I32 => bench::run::<i32>(args, &options),
+            ElementType::I64 => bench::run::<i64>(args, &options),
+            ElementType::F16 => bench::run::<f16>(args, &options),
+            ElementType::Bf16 => bench::run::<bf16>(args, &options),
+        }
+        return;
+    }
+
+    if let Command::Generate(args) = &cli.command {
+        let format = cli.output_format;
+
+        match cli.dtype {
+            ElementType::F32 => generate::run::<f32>(args, format),
+            ElementType::F64 => generate::run::<f64>(args, format),
+            ElementType::I32 => generate::run::<i32>(args, format),
+            ElementType::I64 => generate::run::<i64>(args, format),
+            ElementType::F16 => generate::run::<f16>(args, format),
+            ElementType::Bf16 => generate::run::<bf16>(args, format),
+        }
+        return;
+    }
+
+    if let Command::Verify(args) = &cli.command {
+        let Some(options) = options(&cli) else {
+            return;
+        };
+
+        match cli.dtype {
+            ElementType::F32 => verify::run::<f32>(&cli, args, &options),
+            ElementType::F64 => verify::run::<f64>(&cli, args, &options),
+            ElementType::I32 => verify::run::<i32>(&cli, args, &options),
+            ElementType::I64 => verify::run::<i64>(&cli, args, &options),
+            ElementType::F16 => verify::run::<f16>(&cli, args, &options),
+            ElementType::Bf16 => verify::run::<bf16>(&cli, args, &options),
+        }
+        return;
+    }
+
+    if let Command::Tune(args) = &cli.command {
+        let Some(options) = options(&cli) else {
+            return;
+        };
+
+        match cli.dtype {
+            ElementType::F32 => tune::run::<f32>(args, &options),
+            ElementType::F64 => tune::run::<f64>(args, &options),
+            ElementType::I32 => tune::run::<i32>(args, &options),
+            ElementType::I64 => tune::run::<i64>(args, &options),
+            ElementType::F16 => tune::run::<f16>(args, &options),
+            ElementType::Bf16 => tune::run::<bf16>(args, &options),
+        }
+        return;
+    }
+
+    cli.require_files();
+
+    match cli.dtype {
+        ElementType::F32 => run::<f32>(cli),
+        ElementType::F64 => run::<f64>(cli),
+        ElementType::I32 => run::<i32>(cli),
+        ElementType::I64 => run::<i64>(cli),
+        ElementType::F16 => run::<f16>(cli),
+        ElementType::Bf16 => run::<bf16>(cli),
+    }
+}
+
+/// Options of the multipliers, clears the program cache first if asked to
+///
+/// Is `None` if the cache could not be cleared
+fn options(cli: &Args) -> Option<Options> {
+    let program_cache = if cli.no_cache {
+        ProgramCache::disabled()
+    } else {
+        ProgramCache::default()
+    };
+
+    if cli.clear_cache {
+        if let Err(e) = ProgramCache::default().clear() {
+            eprintln!("unable to clear program cache: {}", e);
+            return None;
+        }
+    }
+
+    let tuning_database = if cli.no_tuning {
+        TuningDatabase::disabled()
+    } else {
+        TuningDatabase::default()
+    };
+
+    Some(Options {
+        program_cache,
+        tuning_database,
+    })
+}
+
+/// Reads the matrices either from the input file or from the files given with `--a` and `--b`
+///
+/// Binary matrices are memory mapped, the README asks not to change input files while they are
+/// being multiplied
+fn read_operands<T: Element>(cli: &Args) -> Result<(Matrix<T>, Matrix<T>)> {
+    let (Some(a), Some(b)) = (&cli.a, &cli.b) else {
+        // clap makes sure there is an input file without `--a` and `--b`
+        let path = Path::new(cli.input.as_deref().unwrap_or_default());
+        // safe because the input files are not modified while the program runs, see above
+        return unsafe { matmul::parse_file_mapped(path) };
+    };
+
+    let csv_options = CsvOptions {
+        delimiter: cli.delimiter,
+        header: cli.header,
+    };
+
+    // safe for the same reason as the input file
+    let m1 = unsafe { matmul::parse_matrix_file_mapped(Path::new(a), &csv_options)? };
+    let m2 = unsafe { matmul::parse_matrix_file_mapped(Path::new(b), &csv_options)? };
+
+    Ok((m1, m2))
+}
+
+/// Multiplies the matrices from the input files treating their elements as `T`
+fn run<T: Element>(cli: Args) {
+    let (m1, m2) = match read_operands::<T>(&cli) {
         Ok(res) => res,
         Err(e) => {
             eprintln!("unable to parse given input file: {}", e);
@@ -29,7 +164,16 @@ fn main() {
         }
     };
 
-    let mut multiplier = match implementation(cli.mode) {
+    let Some(options) = options(&cli) else {
+        return;
+    };
+
+    // every other command is handled in `main`, this is a multiplication
+    let Command::Multiply(mode) = cli.command else {
+        return;
+    };
+
+    let mut multiplier = match implementation_with_options::<T>(mode, &options) {
         Ok(res) => res,
         Err(e) => {
             eprintln!("unable to create multiplier: {}", e);
@@ -38,14 +182,7 @@ fn main() {
     };
 
     match multiplier.info() {
-        Ok(MultiplierInfo::OnDeviceMultiplier) => println!("multiplication does not use OpenCl"),
-        Ok(MultiplierInfo::OpenClMultiplier {
-            device_name,
-            platform_name,
-        }) => {
-            println!("Platform: {}", platform_name);
-            println!("Device: {}", device_name);
-        }
+        Ok(info) => print_info(&info),
         Err(e) => {
             eprintln!("unable to get multiplier info, {}", e);
             return;
@@ -64,12 +201,47 @@ fn main() {
     let MultiplierStat {
         total_time,
         gpu_time,
+        devices,
     } = multiplier.stat().unwrap();
 
     println!("Total time: {}", total_time);
     println!("Kernel time: {}", gpu_time);
 
-    if let Err(e) = fs::write(cli.output, res.to_string()) {
+    for device in devices {
+        println!(
+            "  {}: {} rows, total time {}, kernel time {}",
+            device.name, device.rows, device.total_time, device.gpu_time
+        );
+    }
+
+    if let Some(accuracy) = multiplier.accuracy() {
+        verify::print_accuracy(&accuracy);
+    }
+
+    // unwrap is safe because of `require_files`
+    let output = cli.output.unwrap();
+    if let Err(e) = matmul::write_file_as(Path::new(&output), &res, cli.output_format) {
         eprintln!("unable to write results, {}", e);
     }
 }
+
+fn print_info(info: &MultiplierInfo) {
+    match info {
+        MultiplierInfo::OnDeviceMultiplier => println!("multiplication does not use OpenCl"),
+        MultiplierInfo::SimdMultiplier { isa } => {
+            println!("multiplication does not use OpenCl");
+            println!("Instruction set: {}", isa);
+        }
+        MultiplierInfo::OpenClMultiplier {
+            device_name,
+            platform_name,
+        } => {
+            println!("Platform: {}", platform_name);
+            println!("Device: {}", device_name);
+        }
+        MultiplierInfo::MultiDeviceMultiplier { devices } => {
+            println!("multiplication is split between {} devices", devices.len());
+            devices.iter().for_each(print_info);
+        }
+    }
+}
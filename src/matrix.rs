@@ -1,47 +1,57 @@
 use std::fmt::{Debug, Display};
 
+use super::accuracy::{Accuracy, ElementError, MatrixDiff, Tolerance};
+use super::Element;
+use super::MatmulError;
 use super::Result;
+use super::Storage;
 
-pub struct Matrix {
+pub struct Matrix<T = f32> {
     /// Count of rows of the Matrix
     pub rows: usize,
     /// Count of columns of the Matrix
     pub cols: usize,
-    /// Actual matrix data in the form of a vec
-    pub data: Vec<f32>,
+    /// Actual matrix data, either in memory or memory mapped from a file
+    pub data: Storage<T>,
 }
 
-impl PartialEq for Matrix {
+/// Exact comparison, see [Matrix::approx_eq] and [Matrix::ulps_eq] for results of floating point
+/// arithmetic
+impl<T: Element> PartialEq for Matrix<T> {
     fn eq(&self, other: &Self) -> bool {
-        if self.rows != other.rows || self.cols != other.cols {
-            return false;
-        }
-
-        self.iter().zip(other.data.iter()).all(|(a, b)| {
-            let res = a - b;
-            res.abs() < 0.01
-        })
+        self.rows == other.rows && self.cols == other.cols && self.iter().eq(other.iter())
     }
 }
 
-impl Matrix {
+/// Whether `a` and `b` are at most `abs_tol` apart, or at most `rel_tol` of the larger magnitude
+fn within(a: f64, b: f64, abs_tol: f64, rel_tol: f64) -> bool {
+    let diff = (a - b).abs();
+    a == b || diff <= abs_tol || diff <= rel_tol * a.abs().max(b.abs())
+}
+
+impl<T: Element> Matrix<T> {
     /// Create a Matrix given the rows and cols and data of a matrix
     ///
     /// May fail if provided bad arguments, as in rows * cols != data.len()
-    pub fn create(rows: usize, cols: usize, data: &[f32]) -> Result<Self> {
+    pub fn create(rows: usize, cols: usize, data: &[T]) -> Result<Self> {
+        Self::from_vec(rows, cols, data.to_vec())
+    }
+
+    /// Create a Matrix that takes ownership of `data` instead of copying it, see [Matrix::create]
+    pub fn from_vec(rows: usize, cols: usize, data: Vec<T>) -> Result<Self> {
         if rows * cols != data.len() {
-            let err_msg = format!(
-                "InvalidData, {} * {} != {} (data size)",
+            return Err(MatmulError::DataSize {
                 rows,
                 cols,
-                data.len()
-            );
-            return Err(err_msg.into());
+                len: data.len(),
+            });
         }
 
-        let data = data.to_vec();
-
-        let res = Self { rows, cols, data };
+        let res = Self {
+            rows,
+            cols,
+            data: data.into(),
+        };
 
         Ok(res)
     }
@@ -51,18 +61,30 @@ impl Matrix {
         Self {
             rows,
             cols,
-            data: vec![0f32; rows * cols],
+            data: vec![T::default(); rows * cols].into(),
+        }
+    }
+
+    /// Creates a copy of the Matrix with every element converted to `U`
+    ///
+    /// Conversion goes through `f64`, so it is lossy for 64 bit integers and saturates when
+    /// converting to a smaller integer type
+    pub fn convert<U: Element>(&self) -> Matrix<U> {
+        Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            data: self.iter().map(|el| U::from_f64(el.to_f64())).collect(),
         }
     }
 
     /// Return an iterator to the matrix data
-    pub fn iter(&self) -> std::slice::Iter<'_, f32> {
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
         self.data.iter()
     }
 
     /// Return a mutable iterator to the matrix data
     #[cfg(test)]
-    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, f32> {
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
         self.data.iter_mut()
     }
 
@@ -70,7 +92,7 @@ impl Matrix {
     ///
     /// Can panic if given bad arguments (index out of bounds)
     #[inline]
-    pub fn get(&self, row: usize, col: usize) -> f32 {
+    pub fn get(&self, row: usize, col: usize) -> T {
         self.data[row * self.cols + col]
     }
 
@@ -78,28 +100,111 @@ impl Matrix {
     ///
     /// Can panic if given bad arguments (index out of bounds)
     #[inline]
-    pub fn set(&mut self, row: usize, cols: usize, new: f32) {
+    pub fn set(&mut self, row: usize, cols: usize, new: T) {
         self.data[row * self.cols + cols] = new;
     }
 
+    /// Whether both matrices have the same shape and every pair of elements is at most `abs_tol`
+    /// apart, or at most `rel_tol` of the larger of their magnitudes apart
+    ///
+    /// The absolute tolerance is for elements close to zero, where relative differences are
+    /// meaningless, see [Matrix::product_tolerance] for one that suits products
+    pub fn approx_eq(&self, other: &Self, abs_tol: f64, rel_tol: f64) -> bool {
+        self.rows == other.rows
+            && self.cols == other.cols
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(&a, &b)| within(a.to_f64(), b.to_f64(), abs_tol, rel_tol))
+    }
+
+    /// Whether both matrices have the same shape and every pair of elements is at most
+    /// `max_ulps` representable values apart, see [Element::ulps]
+    pub fn ulps_eq(&self, other: &Self, max_ulps: u64) -> bool {
+        self.rows == other.rows
+            && self.cols == other.cols
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(&a, &b)| a.ulps(b) <= max_ulps)
+    }
+
+    /// Elements that [Matrix::approx_eq] finds too far apart, along with the [Accuracy] of `self`
+    /// when `other` is the expected matrix
+    ///
+    /// Matrices of different shapes are reported as such, with the worst possible accuracy
+    pub fn diff(&self, other: &Self, abs_tol: f64, rel_tol: f64) -> MatrixDiff {
+        let (shape, other_shape) = ((self.rows, self.cols), (other.rows, other.cols));
+        if shape != other_shape {
+            return MatrixDiff {
+                accuracy: Accuracy {
+                    max_abs_error: f64::INFINITY,
+                    max_rel_error: f64::INFINITY,
+                    max_ulps: u64::MAX,
+                    rel_error: f64::INFINITY,
+                    worst: None,
+                },
+                mismatches: vec![],
+                len: 0,
+                shapes: Some((shape, other_shape)),
+            };
+        }
+
+        let mismatches = self
+            .iter()
+            .zip(other.iter())
+            .enumerate()
+            .map(|(index, (&actual, &expected))| ElementError {
+                row: index / self.cols,
+                col: index % self.cols,
+                actual: actual.to_f64(),
+                expected: expected.to_f64(),
+            })
+            .filter(|el| !within(el.actual, el.expected, abs_tol, rel_tol))
+            .collect();
+
+        MatrixDiff {
+            accuracy: Accuracy::compare(self, other),
+            mismatches,
+            len: self.data.len(),
+            shapes: None,
+        }
+    }
+
+    /// Absolute tolerance for elements of products of `self` by `other` that are computed in `T`
+    ///
+    /// The relative error of [Tolerance::for_product] applied to the largest possible partial sum,
+    /// `k` times the product of the largest elements of the operands. It is zero for integers
+    pub fn product_tolerance(&self, other: &Self) -> f64 {
+        let max = |matrix: &Self| {
+            matrix
+                .iter()
+                .fold(0.0f64, |max, el| max.max(el.to_f64().abs()))
+        };
+        let k = self.cols;
+        // unwrap is safe because products always have a relative error bound
+        let rel_error = Tolerance::for_product::<T>(k).rel_error.unwrap();
+
+        rel_error * k as f64 * max(self) * max(other)
+    }
+
     /// Creates a Matrix from self that is padded out with zeroes so that the new dimensions are
     /// divisible by `tile`
     ///
     /// This is an optimization for various implementations
-    pub fn create_zero_padded(&self, tile: usize) -> Matrix {
-        if self.rows % tile == 0 && self.cols == 0 {
+    pub fn create_zero_padded(&self, tile: usize) -> Matrix<T> {
+        // empty matrices still get a single tile, kernels can not be launched with no work items
+        let new_rows = self.rows.next_multiple_of(tile).max(tile);
+        let new_cols = self.cols.next_multiple_of(tile).max(tile);
+
+        if new_rows == self.rows && new_cols == self.cols {
             return Matrix {
                 rows: self.rows,
                 cols: self.cols,
-                data: self.data.clone(),
+                data: self.data.to_vec().into(),
             };
         }
 
-        let mod_rows = self.rows % tile;
-        let mod_cols = self.cols % tile;
-        let new_rows = self.rows - mod_rows + tile;
-        let new_cols = self.cols - mod_cols + tile;
-
         let mut res = Matrix::create_empty(new_rows, new_cols);
 
         for i in 0..self.rows {
@@ -112,7 +217,7 @@ impl Matrix {
     }
 
     /// Returns a new trimmed matrix from self provided a new row and column size
-    pub fn create_trimmed(&self, rows: usize, cols: usize) -> Matrix {
+    pub fn create_trimmed(&self, rows: usize, cols: usize) -> Matrix<T> {
         let mut res = Matrix::create_empty(rows, cols);
 
         for i in 0..rows {
@@ -126,13 +231,13 @@ impl Matrix {
     }
 }
 
-impl Display for Matrix {
+impl<T: Element> Display for Matrix<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         Debug::fmt(&self, f)
     }
 }
 
-impl Debug for Matrix {
+impl<T: Element> Debug for Matrix<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for i in 0..self.rows {
             for j in 0..self.cols {
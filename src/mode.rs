@@ -0,0 +1,213 @@
+use std::fmt::{self, Display};
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+
+use clap::{Args, Subcommand, ValueEnum};
+use regex::Regex;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum DeviceType {
+    /// Dedicated gpu
+    DGpu = 0,
+    /// Integrated gpu
+    IGpu = 1,
+    /// Any gpu
+    Gpu = 2,
+    /// Any cpu
+    Cpu = 3,
+    /// Any device found
+    #[default]
+    All = 4,
+}
+
+/// OpenCl device an implementation runs on
+///
+/// Every criterion that is given narrows down the devices, `index` then picks one of the remaining
+/// ones. Without any criteria this is the first device found
+#[derive(Clone, Debug, Default, Args)]
+pub struct DeviceSelector {
+    /// Type of the device, any type by default
+    pub device_type: Option<DeviceType>,
+    /// Index among the devices that match every other criterion, 0 by default
+    pub index: Option<usize>,
+    /// `<platform>:<device>` indices as listed by the devices command, or a regex that matches
+    /// the device name or vendor
+    #[arg(long, env = "MATMUL_DEVICE")]
+    pub device: Option<DeviceMatch>,
+    /// Regex that matches the platform name
+    #[arg(long, env = "MATMUL_PLATFORM")]
+    pub platform: Option<Regex>,
+}
+
+impl DeviceSelector {
+    /// The first device found, same as [DeviceSelector::default]
+    pub const ANY: Self = Self {
+        device_type: None,
+        index: None,
+        device: None,
+        platform: None,
+    };
+
+    /// The device at `index` among the ones of type `device_type`
+    pub const fn new(device_type: DeviceType, index: usize) -> Self {
+        Self {
+            device_type: Some(device_type),
+            index: Some(index),
+            device: None,
+            platform: None,
+        }
+    }
+}
+
+impl Display for DeviceSelector {
+    /// Criteria that are given separated by commas, `any device` if there are none
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut criteria = vec![];
+
+        if let Some(device_type) = self.device_type {
+            criteria.push(format!("type {device_type:?}"));
+        }
+        if let Some(index) = self.index {
+            criteria.push(format!("index {index}"));
+        }
+        match &self.device {
+            Some(DeviceMatch::Position { platform, device }) => {
+                criteria.push(format!("device {platform}:{device}"))
+            }
+            Some(DeviceMatch::Name(regex)) => {
+                criteria.push(format!("name or vendor matching `{regex}`"))
+            }
+            None => (),
+        }
+        if let Some(regex) = &self.platform {
+            criteria.push(format!("platform matching `{regex}`"));
+        }
+
+        if criteria.is_empty() {
+            return write!(f, "any device");
+        }
+
+        write!(f, "{}", criteria.join(", "))
+    }
+}
+
+/// Device given with `--device`
+#[derive(Clone, Debug)]
+pub enum DeviceMatch {
+    /// Device `device` of platform `platform`, as listed by [platforms](crate::platforms)
+    Position { platform: usize, device: usize },
+    /// Any device whose name or vendor matches
+    Name(Regex),
+}
+
+impl FromStr for DeviceMatch {
+    type Err = regex::Error;
+
+    /// `<platform>:<device>` if both are numbers, a regex otherwise
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let position = s
+            .split_once(':')
+            .and_then(|(platform, device)| Some((platform.parse().ok()?, device.parse().ok()?)));
+
+        match position {
+            Some((platform, device)) => Ok(Self::Position { platform, device }),
+            None => Ok(Self::Name(Regex::new(s)?)),
+        }
+    }
+}
+
+/// Implementations that [Mode::Strassen] can hand its blocks to
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum Leaf {
+    Basic,
+    Cpu,
+    #[default]
+    Simd,
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Leaf {
+    /// Mode of the leaf implementation, OpenCl ones use the device picked by `device`
+    pub fn mode(self, device: DeviceSelector) -> Mode {
+        match self {
+            Self::Basic => Mode::Basic,
+            Self::Cpu => Mode::Cpu { threads: None },
+            Self::Simd => Mode::Simd { threads: None },
+            Self::Easy => Mode::Easy { device },
+            Self::Medium => Mode::Medium { device },
+            Self::Hard => Mode::Hard { device },
+        }
+    }
+}
+
+/// OpenCl implementations that [Mode::Multi] can run on every device
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum Kernel {
+    Easy,
+    Medium,
+    #[default]
+    Hard,
+}
+
+/// 8 implementations are provided as of time of writing
+#[derive(Clone, Debug, Subcommand)]
+pub enum Mode {
+    /// Basic implementation is just 3 loops on the host
+    Basic,
+    /// Cpu implementation is a cache blocked implementation that uses every core of the host
+    Cpu {
+        /// Number of threads, one per available core by default
+        #[arg(short, long)]
+        threads: Option<NonZeroUsize>,
+    },
+    /// Simd implementation is the cpu implementation with vectorized microkernels
+    Simd {
+        /// Number of threads, one per available core by default
+        #[arg(short, long)]
+        threads: Option<NonZeroUsize>,
+    },
+    /// Strassen implementation recursively splits the matrices and multiplies the blocks with another
+    /// implementation
+    Strassen {
+        /// Implementation that multiplies the blocks
+        #[arg(short, long, value_enum, default_value_t)]
+        leaf: Leaf,
+        /// Blocks with a dimension of at most this size are not split any further
+        #[arg(short, long, default_value_t = 256)]
+        crossover: usize,
+        /// Compare the result with the one of the basic implementation
+        #[arg(short, long)]
+        accuracy: bool,
+        #[command(flatten)]
+        device: DeviceSelector,
+    },
+    /// Easy implementation is a naive implementation that uses the gpu
+    Easy {
+        #[command(flatten)]
+        device: DeviceSelector,
+    },
+    /// Medium implementation is a less naive implementation that uses local memory on the gpu
+    Medium {
+        #[command(flatten)]
+        device: DeviceSelector,
+    },
+    /// Hard is an okay implementation that optimized thread throughput
+    Hard {
+        #[command(flatten)]
+        device: DeviceSelector,
+    },
+    /// Multi implementation splits the rows of the result between several OpenCl devices, every
+    /// device that matches is used unless an index is given
+    Multi {
+        /// Implementation that runs on every device
+        #[arg(short, long, value_enum, default_value_t)]
+        kernel: Kernel,
+        #[command(flatten)]
+        device: DeviceSelector,
+    },
+}
@@ -1,58 +1,331 @@
-use super::args::Mode;
+use super::accuracy::Accuracy;
+use super::cache::ProgramCache;
+use super::mode::Mode;
+use super::tuning::TuningDatabase;
+use super::Element;
+use super::MatmulError;
 use super::Matrix;
 use super::Result;
 
-use super::implementations::{BasicMultiplier, EasyMultiplier, HardMultiplier, MediumMultiplier};
+use super::implementations::{BasicMultiplier, CpuMultiplier, Isa, SimdMultiplier};
+use super::implementations::{
+    EasyMultiplier, HardMultiplier, MediumMultiplier, MultiDeviceMultiplier, StrassenMultiplier,
+};
+
+/// Whether an operand of [Multiplier::gemm] is used as is or transposed
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Transpose {
+    #[default]
+    No,
+    Yes,
+}
+
+impl Transpose {
+    /// `(rows, cols)` of the operand after the transposition
+    pub fn shape<T>(self, matrix: &Matrix<T>) -> (usize, usize) {
+        match self {
+            Self::No => (matrix.rows, matrix.cols),
+            Self::Yes => (matrix.cols, matrix.rows),
+        }
+    }
+}
+
+/// Scalars and transpositions of [Multiplier::gemm]
+#[derive(Copy, Clone, Debug)]
+pub struct Gemm<T> {
+    pub alpha: T,
+    pub beta: T,
+    pub trans_a: Transpose,
+    pub trans_b: Transpose,
+}
+
+impl<T: Element> Default for Gemm<T> {
+    /// Plain `C = A * B`
+    fn default() -> Self {
+        Self {
+            alpha: T::from_f64(1.0),
+            beta: T::default(),
+            trans_a: Transpose::No,
+            trans_b: Transpose::No,
+        }
+    }
+}
+
+/// `count` matrices of the same shape stored in a single slice, matrix `i` starts at `i * stride`
+#[derive(Copy, Clone, Debug)]
+pub struct StridedBatch<'a, T> {
+    pub data: &'a [T],
+    pub rows: usize,
+    pub cols: usize,
+    /// Distance in elements between the first elements of consecutive matrices
+    pub stride: usize,
+    pub count: usize,
+}
+
+impl<'a, T: Element> StridedBatch<'a, T> {
+    /// Batch of matrices that are stored back to back
+    pub fn new(data: &'a [T], rows: usize, cols: usize, count: usize) -> Self {
+        Self {
+            data,
+            rows,
+            cols,
+            stride: rows * cols,
+            count,
+        }
+    }
+
+    /// Data of matrix `index` of the batch
+    ///
+    /// Can panic if given bad arguments (index out of bounds)
+    pub fn get(&self, index: usize) -> &'a [T] {
+        let start = index * self.stride;
+        &self.data[start..start + self.rows * self.cols]
+    }
+
+    /// Part of `data` that is actually used by the batch
+    pub fn used(&self) -> &'a [T] {
+        match self.count {
+            0 => &[],
+            count => &self.data[..(count - 1) * self.stride + self.rows * self.cols],
+        }
+    }
+
+    /// Makes sure that every matrix of the batch fits in `data`
+    fn check(&self) -> Result<()> {
+        let len = match self.count {
+            0 => 0,
+            count => (count - 1) * self.stride + self.rows * self.cols,
+        };
+
+        if len > self.data.len() {
+            let msg = format!(
+                "{} matrices with stride {} need {} elements, got {}",
+                self.count,
+                self.stride,
+                len,
+                self.data.len()
+            );
+            return Err(MatmulError::InvalidBatch(msg));
+        }
+
+        Ok(())
+    }
+}
+
+/// Makes sure that every matrix of `a` can be multiplied by the matrix of `b` with the same index
+pub(crate) fn check_batches<T: Element>(a: &StridedBatch<T>, b: &StridedBatch<T>) -> Result<()> {
+    a.check()?;
+    b.check()?;
+
+    if a.count != b.count {
+        let msg = format!("batch sizes differ, {} != {}", a.count, b.count);
+        return Err(MatmulError::InvalidBatch(msg));
+    }
+
+    if a.cols != b.rows {
+        return Err(MatmulError::DimensionMismatch {
+            left: (a.rows, a.cols),
+            right: (b.rows, b.cols),
+        });
+    }
+
+    Ok(())
+}
 
 /// Anyone who implements this trait will have the ability to multiply matrices
-pub trait Multiplier {
+pub trait Multiplier<T: Element = f32> {
     /// Info on the devices that are performing multiplication
     fn info(&self) -> Result<MultiplierInfo>;
+    /// Computes `C = alpha * op(A) * op(B) + beta * C` in place, where `op` is either nothing or a
+    /// transposition, as in BLAS `gemm`
+    ///
+    /// `C` is not read when `beta` is zero
+    fn gemm(
+        &mut self,
+        params: Gemm<T>,
+        a: &Matrix<T>,
+        b: &Matrix<T>,
+        c: &mut Matrix<T>,
+    ) -> Result<()>;
     /// Multiply two matrices
-    fn multiply(&mut self, m1: &Matrix, m2: &Matrix) -> Result<Matrix>;
+    fn multiply(&mut self, m1: &Matrix<T>, m2: &Matrix<T>) -> Result<Matrix<T>> {
+        let mut res = Matrix::create_empty(m1.rows, m2.cols);
+        self.gemm(Gemm::default(), m1, m2, &mut res)?;
+
+        Ok(res)
+    }
+    /// Multiply every matrix of `a` by the matrix of `b` with the same index
+    ///
+    /// If all matrices of `a` and all matrices of `b` have the same shape they are multiplied with
+    /// [Multiplier::multiply_strided_batch], otherwise one by one
+    fn multiply_batch(&mut self, a: &[Matrix<T>], b: &[Matrix<T>]) -> Result<Vec<Matrix<T>>> {
+        if a.len() != b.len() {
+            let msg = format!("batch sizes differ, {} != {}", a.len(), b.len());
+            return Err(MatmulError::InvalidBatch(msg));
+        }
+
+        let (Some(first_a), Some(first_b)) = (a.first(), b.first()) else {
+            return Ok(vec![]);
+        };
+
+        let same_shape =
+            |m: &Matrix<T>, first: &Matrix<T>| (m.rows, m.cols) == (first.rows, first.cols);
+        if !a.iter().all(|m| same_shape(m, first_a)) || !b.iter().all(|m| same_shape(m, first_b)) {
+            return a
+                .iter()
+                .zip(b)
+                .map(|(m1, m2)| self.multiply(m1, m2))
+                .collect();
+        }
+
+        let data_a = a.iter().flat_map(|m| m.iter().copied()).collect::<Vec<_>>();
+        let data_b = b.iter().flat_map(|m| m.iter().copied()).collect::<Vec<_>>();
+        let batch_a = StridedBatch::new(&data_a, first_a.rows, first_a.cols, a.len());
+        let batch_b = StridedBatch::new(&data_b, first_b.rows, first_b.cols, b.len());
+
+        let res = self.multiply_strided_batch(&batch_a, &batch_b)?;
+
+        let (rows, cols) = (first_a.rows, first_b.cols);
+        if rows * cols == 0 {
+            return Ok(a.iter().map(|_| Matrix::create_empty(rows, cols)).collect());
+        }
+
+        res.chunks(rows * cols)
+            .map(|data| Matrix::create(rows, cols, data))
+            .collect()
+    }
+    /// Multiply every matrix of `a` by the matrix of `b` with the same index
+    ///
+    /// Results are stored back to back, matrix `i` starts at `i * a.rows * b.cols`
+    fn multiply_strided_batch(
+        &mut self,
+        a: &StridedBatch<T>,
+        b: &StridedBatch<T>,
+    ) -> Result<Vec<T>> {
+        check_batches(a, b)?;
+
+        let mut res = Vec::with_capacity(a.count * a.rows * b.cols);
+
+        for index in 0..a.count {
+            let m1 = Matrix::create(a.rows, a.cols, a.get(index))?;
+            let m2 = Matrix::create(b.rows, b.cols, b.get(index))?;
+            res.extend_from_slice(&self.multiply(&m1, &m2)?.data);
+        }
+
+        Ok(res)
+    }
     /// Gives statistics on the last run of multiplier.
     ///
     /// Is `None` if the [Multiply] hasn't yet been used
     fn stat(&self) -> Option<MultiplierStat>;
+    /// How far the result of the last run is from the one of [BasicMultiplier]
+    ///
+    /// Is `None` unless the multiplier trades precision for speed and was asked to measure it
+    fn accuracy(&self) -> Option<Accuracy> {
+        None
+    }
 }
 
 /// Matrix multiplication can happen on device or on the gpu
+#[derive(Clone, Debug)]
 pub enum MultiplierInfo {
     OnDeviceMultiplier,
+    /// Multiplication is done on the host with vectorized microkernels
+    SimdMultiplier {
+        /// Instruction set the microkernels use
+        isa: Isa,
+    },
     OpenClMultiplier {
         /// If matrix multiplication is done using OpenCl then this is the name of the device
         device_name: String,
         /// If matrix multiplication is done using OpenCl then this is the name of the platform
         platform_name: String,
     },
+    /// Multiplication is split between several multipliers, see
+    /// [MultiDeviceMultiplier](crate::MultiDeviceMultiplier)
+    MultiDeviceMultiplier {
+        /// Info of every multiplier, in the order in which they get rows of the result
+        devices: Vec<MultiplierInfo>,
+    },
 }
 
 /// Run statistics for multiplication
+#[derive(Clone, Debug)]
 pub struct MultiplierStat {
     /// Total time of execution
     pub total_time: u64,
     /// Kernel time, is zero if multiplication happens on device
     pub gpu_time: u64,
+    /// Statistics of every device that got a part of the work, empty unless the multiplication is
+    /// split between devices
+    pub devices: Vec<DeviceStat>,
+}
+
+/// Run statistics of a single device of a split multiplication
+#[derive(Clone, Debug)]
+pub struct DeviceStat {
+    /// Name of the device
+    pub name: String,
+    /// Rows of the result computed by the device
+    pub rows: usize,
+    /// Time the device took, including transfers
+    pub total_time: u64,
+    /// Kernel time of the device
+    pub gpu_time: u64,
+}
+
+/// Settings shared by every multiplier that [implementation_with_options] creates
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Where compiled OpenCl programs are cached
+    pub program_cache: ProgramCache,
+    /// Where the hard implementation finds the kernel config of its device
+    pub tuning_database: TuningDatabase,
 }
 
 /// Provided a mode return a multipliplier trait object
-pub fn implementation(mode: Mode) -> Result<Box<dyn Multiplier>> {
+pub fn implementation<T: Element>(mode: Mode) -> Result<Box<dyn Multiplier<T>>> {
+    implementation_with_options(mode, &Options::default())
+}
+
+/// Same as [implementation], but with non default [Options]
+pub fn implementation_with_options<T: Element>(
+    mode: Mode,
+    options: &Options,
+) -> Result<Box<dyn Multiplier<T>>> {
+    let program_cache = options.program_cache.clone();
+
     match mode {
         Mode::Basic => Ok(Box::new(BasicMultiplier::default())),
-        Mode::Easy { device_type, index } => {
-            let device_type = device_type.unwrap_or_default();
-            let index = index.unwrap_or_default();
-            Ok(Box::new(EasyMultiplier::new(device_type, index)?))
-        }
-        Mode::Medium { device_type, index } => {
-            let device_type = device_type.unwrap_or_default();
-            let index = index.unwrap_or_default();
-            Ok(Box::new(MediumMultiplier::new(device_type, index)?))
-        }
-        Mode::Hard { device_type, index } => {
-            let device_type = device_type.unwrap_or_default();
-            let index = index.unwrap_or_default();
-            Ok(Box::new(HardMultiplier::new(device_type, index)?))
+        Mode::Cpu { threads } => Ok(Box::new(CpuMultiplier::new(threads))),
+        Mode::Simd { threads } => Ok(Box::new(SimdMultiplier::new(threads))),
+        Mode::Strassen {
+            leaf,
+            crossover,
+            accuracy,
+            device,
+        } => {
+            let leaf = implementation_with_options(leaf.mode(device), options)?;
+            let multiplier = StrassenMultiplier::new(leaf, crossover);
+            Ok(Box::new(multiplier.with_accuracy_check(accuracy)))
+        }
+        Mode::Easy { device } => {
+            let multiplier = EasyMultiplier::from_selector(&device)?;
+            Ok(Box::new(multiplier.with_program_cache(program_cache)))
+        }
+        Mode::Medium { device } => {
+            let multiplier = MediumMultiplier::from_selector(&device)?;
+            Ok(Box::new(multiplier.with_program_cache(program_cache)))
+        }
+        Mode::Hard { device } => {
+            let multiplier =
+                HardMultiplier::from_tuning_database(&device, &options.tuning_database)?
+                    .with_program_cache(program_cache);
+            Ok(Box::new(multiplier))
+        }
+        Mode::Multi { kernel, device } => {
+            let multiplier = MultiDeviceMultiplier::from_selector(kernel, &device, options)?;
+            Ok(Box::new(multiplier))
         }
     }
 }
@@ -0,0 +1,128 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::element::{as_bytes, as_bytes_mut};
+use crate::Element;
+use crate::ElementType;
+use crate::MatmulError;
+use crate::Matrix;
+use crate::Result;
+use crate::Storage;
+
+/// First bytes of every binary matrix
+pub const MAGIC: [u8; 4] = *b"MMAT";
+/// Version of the layout written by [write]
+pub const VERSION: u16 = 1;
+
+/// Size of the header in bytes: magic, version, element type, rows and cols
+const HEADER_SIZE: usize = 4 + 2 + 2 + 8 + 8;
+
+/// Whether `bytes` start like a binary matrix
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Reads a single binary matrix, little endian header followed by the little endian elements in
+/// row major order
+pub fn read<T: Element>(reader: &mut impl Read) -> Result<Matrix<T>> {
+    let (rows, cols) = read_header::<T>(reader)?;
+
+    let mut data = super::read_elements::<T>(reader, rows * cols, "binary")?;
+
+    if cfg!(target_endian = "big") {
+        swap_bytes(as_bytes_mut(&mut data), std::mem::size_of::<T>());
+    }
+
+    Matrix::from_vec(rows, cols, data)
+}
+
+/// Maps the binary matrix at byte `offset` of `file` instead of reading it, see [read]
+///
+/// Returns the matrix and the offset right after it. Elements are used as they are in the file,
+/// so this is only possible on little endian hosts
+///
+/// # Safety
+///
+/// The file must not be modified or truncated while the matrix is alive, see [Storage::map]
+pub(super) unsafe fn map<T: Element>(file: &File, offset: u64) -> Result<(Matrix<T>, u64)> {
+    let mut reader = file;
+    reader.seek(SeekFrom::Start(offset))?;
+    let (rows, cols) = read_header::<T>(&mut reader)?;
+
+    let start = offset + HEADER_SIZE as u64;
+    let data = Storage::map(file, start, rows * cols)?;
+    let end = start + std::mem::size_of_val(&*data) as u64;
+
+    Ok((Matrix { rows, cols, data }, end))
+}
+
+/// Reads the header of a binary matrix holding elements of type `T`, returns its rows and cols
+fn read_header<T: Element>(reader: &mut impl Read) -> Result<(usize, usize)> {
+    let mut header = [0; HEADER_SIZE];
+    reader.read_exact(&mut header)?;
+
+    if !is_binary(&header) {
+        return Err(format_error("bad magic bytes"));
+    }
+
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != VERSION {
+        return Err(format_error(format!("unsupported version {version}")));
+    }
+
+    let tag = u16::from_le_bytes([header[6], header[7]]);
+    match ElementType::from_tag(tag) {
+        Some(ty) if ty == T::TYPE => (),
+        Some(ty) => {
+            let msg = format!("elements are {ty:?}, expected {:?}", T::TYPE);
+            return Err(format_error(msg));
+        }
+        None => return Err(format_error(format!("unknown element type {tag}"))),
+    }
+
+    // unwraps are safe because the slices are 8 bytes long
+    let rows = u64::from_le_bytes(header[8..16].try_into().unwrap());
+    let cols = u64::from_le_bytes(header[16..24].try_into().unwrap());
+
+    let len = usize::try_from(rows)
+        .ok()
+        .zip(usize::try_from(cols).ok())
+        .and_then(|(rows, cols)| rows.checked_mul(cols));
+    let size = len.and_then(|len| len.checked_mul(std::mem::size_of::<T>()));
+    if size.is_none() {
+        return Err(format_error(format!("{rows}x{cols} matrix is too large")));
+    }
+
+    Ok((rows as usize, cols as usize))
+}
+
+/// Writes a single matrix in the format that [read] expects
+pub fn write<T: Element>(writer: &mut impl Write, matrix: &Matrix<T>) -> Result<()> {
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(T::TYPE as u16).to_le_bytes())?;
+    writer.write_all(&(matrix.rows as u64).to_le_bytes())?;
+    writer.write_all(&(matrix.cols as u64).to_le_bytes())?;
+
+    if cfg!(target_endian = "big") {
+        let mut bytes = as_bytes(&matrix.data).to_vec();
+        swap_bytes(&mut bytes, std::mem::size_of::<T>());
+        writer.write_all(&bytes)?;
+    } else {
+        writer.write_all(as_bytes(&matrix.data))?;
+    }
+
+    Ok(())
+}
+
+/// Reverses the bytes of every element of size `size`
+fn swap_bytes(bytes: &mut [u8], size: usize) {
+    bytes.chunks_mut(size).for_each(|el| el.reverse());
+}
+
+fn format_error(message: impl Into<String>) -> MatmulError {
+    MatmulError::Format {
+        format: "binary",
+        message: message.into(),
+    }
+}
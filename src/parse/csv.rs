@@ -0,0 +1,116 @@
+use std::io::{BufRead, Write};
+
+use crate::Element;
+use crate::MatmulError;
+use crate::Matrix;
+use crate::Result;
+
+/// Delimiters that are looked for in the first line when none is given, in order
+const DELIMITERS: [char; 3] = ['\t', ',', ';'];
+
+/// How CSV and TSV files are read
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Separator of the values. If `None` it is detected from the first line: a tab, a comma or a
+    /// semicolon, whichever comes first, and runs of whitespace otherwise
+    pub delimiter: Option<char>,
+    /// Whether the first line names the columns. If `None` it does if any of its values is not a
+    /// number
+    pub header: Option<bool>,
+}
+
+/// Reads a single matrix with one row per line, its shape is inferred from the values
+///
+/// Blank lines are skipped and values may be surrounded by double quotes
+pub fn read<T: Element>(reader: &mut impl BufRead, options: &CsvOptions) -> Result<Matrix<T>> {
+    let mut delimiter = options.delimiter;
+    let mut cols = None;
+    let mut rows = 0;
+    let mut data = vec![];
+
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_no = idx + 1;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let delimiter = *delimiter.get_or_insert_with(|| detect_delimiter(&line));
+        let fields = split(&line, delimiter);
+
+        if rows == 0 && cols.is_none() {
+            let is_header = options.header.unwrap_or_else(|| {
+                fields
+                    .iter()
+                    .any(|(_, field)| field.parse::<f64>().is_err())
+            });
+
+            if is_header {
+                cols = Some(fields.len());
+                continue;
+            }
+        }
+
+        let expected = *cols.get_or_insert(fields.len());
+        if fields.len() != expected {
+            return Err(super::count_error(&line, line_no, &fields, expected));
+        }
+
+        for field in fields {
+            data.push(super::parse_token(field, line_no)?);
+        }
+
+        rows += 1;
+    }
+
+    if rows == 0 {
+        return Err(MatmulError::Format {
+            format: "csv",
+            message: "file has no values".to_string(),
+        });
+    }
+
+    Matrix::from_vec(rows, cols.unwrap_or_default(), data)
+}
+
+/// Writes a matrix with one row per line and values separated by `delimiter`
+pub fn write<T: Element>(
+    writer: &mut impl Write,
+    matrix: &Matrix<T>,
+    delimiter: char,
+) -> Result<()> {
+    for row in matrix.data.chunks(matrix.cols.max(1)) {
+        let line = row
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(&delimiter.to_string());
+        writeln!(writer, "{line}")?;
+    }
+
+    Ok(())
+}
+
+fn detect_delimiter(line: &str) -> char {
+    DELIMITERS
+        .into_iter()
+        .find(|&delimiter| line.contains(delimiter))
+        .unwrap_or(' ')
+}
+
+/// Values of `line` without quotes and their columns, a space delimiter splits on any whitespace
+fn split(line: &str, delimiter: char) -> Vec<(usize, &str)> {
+    let fields: Box<dyn Iterator<Item = &str>> = if delimiter == ' ' {
+        Box::new(line.split_whitespace())
+    } else {
+        Box::new(line.split(delimiter))
+    };
+
+    fields
+        .map(|field| {
+            let field = field.trim().trim_matches('"');
+            (super::column(line, field), field)
+        })
+        .collect()
+}
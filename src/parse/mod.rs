@@ -0,0 +1,356 @@
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+
+use super::element::as_bytes_mut;
+use super::Element;
+use super::MatmulError;
+use super::Matrix;
+use super::Result;
+
+mod binary;
+mod csv;
+mod mtx;
+mod npy;
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests;
+mod text;
+
+pub use binary::{read as read_binary, write as write_binary};
+pub use csv::{read as read_csv, write as write_csv, CsvOptions};
+pub use mtx::{read as read_mtx, read_sparse as read_mtx_sparse};
+pub use mtx::{write as write_mtx, write_sparse as write_mtx_sparse};
+pub use npy::{read as read_npy, read_npz, write as write_npy, write_npz};
+
+/// Formats that matrices can be written in
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum FileFormat {
+    /// Space separated values, one row per line
+    #[default]
+    Text,
+    /// Header followed by raw little endian elements, see [read_binary]
+    Binary,
+    /// NumPy `.npy`, see [write_npy]
+    Npy,
+    /// Matrix Market array, see [write_mtx]
+    Mtx,
+    /// Comma separated values, one row per line
+    Csv,
+}
+
+/// Parses a file for two matrices
+///
+/// The format is detected from the contents: two binary matrices one after another, a NumPy
+/// `.npz` with two arrays, see [read_npz], or text with a `n m k` header followed by both matrices
+///
+/// Binary matrices are read into memory, see [parse_file_mapped] to map them instead
+pub fn parse_file<T: Element>(path: &Path) -> Result<(Matrix<T>, Matrix<T>)> {
+    read_operands(path, false).map_err(|e| e.in_file(path))
+}
+
+/// Parses a file for two matrices like [parse_file], but memory maps binary matrices on little
+/// endian hosts instead of reading them
+///
+/// The mapping is copy on write, changing the matrices never modifies the file
+///
+/// # Safety
+///
+/// The file must not be modified or truncated, by this or any other process, while the matrices
+/// are alive
+pub unsafe fn parse_file_mapped<T: Element>(path: &Path) -> Result<(Matrix<T>, Matrix<T>)> {
+    read_operands(path, true).map_err(|e| e.in_file(path))
+}
+
+fn read_operands<T: Element>(path: &Path, map: bool) -> Result<(Matrix<T>, Matrix<T>)> {
+    let file = fs::File::open(path)?;
+    let mut reader = io::BufReader::new(file);
+
+    let start = reader.fill_buf()?;
+    if npy::is_npz(start) {
+        return npy::read_npz(reader);
+    }
+    if npy::is_npy(start) {
+        return Err(MatmulError::Format {
+            format: "npy",
+            message: "file holds a single array, both operands must be in a .npz".to_string(),
+        });
+    }
+    if mtx::is_mtx(start) {
+        return Err(MatmulError::Format {
+            format: "Matrix Market",
+            message: "file holds a single matrix, both operands are expected".to_string(),
+        });
+    }
+    if !binary::is_binary(start) {
+        return text::read(&mut reader);
+    }
+
+    if map && cfg!(target_endian = "little") {
+        let file = reader.into_inner();
+        // safe because the caller of [parse_file_mapped] does not modify the file
+        let (m1, offset) = unsafe { binary::map(&file, 0)? };
+        let (m2, offset) = unsafe { binary::map(&file, offset)? };
+        expect_len(&file, offset, "binary")?;
+        return Ok((m1, m2));
+    }
+
+    let m1 = binary::read(&mut reader)?;
+    let m2 = binary::read(&mut reader)?;
+    expect_end(&mut reader, "binary")?;
+
+    Ok((m1, m2))
+}
+
+/// Parses a file for a single matrix
+///
+/// The format is detected from the contents: a binary matrix, a NumPy `.npy`, a Matrix Market
+/// file or CSV read with `csv_options`. Plain text with one row per line is CSV without a header
+/// and with whitespace as the delimiter
+///
+/// Binary matrices are read into memory, see [parse_matrix_file_mapped] to map them instead
+pub fn parse_matrix_file<T: Element>(path: &Path, csv_options: &CsvOptions) -> Result<Matrix<T>> {
+    read_matrix(path, csv_options, false).map_err(|e| e.in_file(path))
+}
+
+/// Parses a file for a single matrix like [parse_matrix_file], but memory maps a binary matrix on
+/// little endian hosts like [parse_file_mapped]
+///
+/// # Safety
+///
+/// The file must not be modified or truncated, by this or any other process, while the matrix is
+/// alive
+pub unsafe fn parse_matrix_file_mapped<T: Element>(
+    path: &Path,
+    csv_options: &CsvOptions,
+) -> Result<Matrix<T>> {
+    read_matrix(path, csv_options, true).map_err(|e| e.in_file(path))
+}
+
+fn read_matrix<T: Element>(path: &Path, csv_options: &CsvOptions, map: bool) -> Result<Matrix<T>> {
+    let file = fs::File::open(path)?;
+    let mut reader = io::BufReader::new(file);
+
+    let start = reader.fill_buf()?;
+    if npy::is_npz(start) {
+        return Err(MatmulError::Format {
+            format: "npz",
+            message: "file holds several arrays, a single .npy is expected".to_string(),
+        });
+    }
+    if npy::is_npy(start) {
+        let res = npy::read(&mut reader)?;
+        expect_end(&mut reader, "npy")?;
+        return Ok(res);
+    }
+    if mtx::is_mtx(start) {
+        return mtx::read(&mut reader);
+    }
+    if !binary::is_binary(start) {
+        return csv::read(&mut reader, csv_options);
+    }
+
+    if map && cfg!(target_endian = "little") {
+        let file = reader.into_inner();
+        // safe because the caller of [parse_matrix_file_mapped] does not modify the file
+        let (res, offset) = unsafe { binary::map(&file, 0)? };
+        expect_len(&file, offset, "binary")?;
+        return Ok(res);
+    }
+
+    let res = binary::read(&mut reader)?;
+    expect_end(&mut reader, "binary")?;
+
+    Ok(res)
+}
+
+/// Whitespace separated tokens of `line` and their columns
+fn tokens(line: &str) -> Vec<(usize, &str)> {
+    line.split_whitespace()
+        .map(|token| (column(line, token), token))
+        .collect()
+}
+
+/// Column of `token`, which must be a slice of `line`, counted in characters from 1
+fn column(line: &str, token: &str) -> usize {
+    let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+    line[..offset].chars().count() + 1
+}
+
+/// Parses a token found by [tokens]
+fn parse_token<T>((column, token): (usize, &str), line_no: usize) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    token.parse::<T>().map_err(|e| MatmulError::Parse {
+        line: line_no,
+        column,
+        message: format!("`{token}`, {e}"),
+    })
+}
+
+/// Error for line `line_no` having other than `expected` tokens
+///
+/// Points at the first extra token or right after the last one if there are too few
+fn count_error(
+    line: &str,
+    line_no: usize,
+    tokens: &[(usize, &str)],
+    expected: usize,
+) -> MatmulError {
+    let found = tokens.len();
+    let (column, message) = match (tokens.get(expected), tokens.last()) {
+        (Some(&(column, token)), _) => (
+            column,
+            format!("expected {expected} values, found {found}, `{token}` is the first extra one"),
+        ),
+        (None, Some(&(_, token))) => (
+            line.trim_end().chars().count() + 1,
+            format!("expected {expected} values, found {found}, the line ends after `{token}`"),
+        ),
+        (None, None) => (1, format!("expected {expected} values, found none")),
+    };
+
+    MatmulError::Parse {
+        line: line_no,
+        column,
+        message,
+    }
+}
+
+/// Reads `len` elements as they are in `reader`, in the byte order of the host
+///
+/// The buffer grows with the elements that are actually read, so a header that claims more
+/// elements than the input holds fails with a `format` error instead of a huge allocation
+fn read_elements<T: Element>(
+    reader: &mut impl Read,
+    len: usize,
+    format: &'static str,
+) -> Result<Vec<T>> {
+    // elements allocated ahead of the ones that are read, doubled as the input keeps going
+    const CHUNK: usize = 1 << 16;
+
+    let mut data = Vec::new();
+    while data.len() < len {
+        let start = data.len();
+        data.resize(len.min(start + start.max(CHUNK)), T::default());
+
+        if let Err(e) = reader.read_exact(as_bytes_mut(&mut data[start..])) {
+            if e.kind() != io::ErrorKind::UnexpectedEof {
+                return Err(e.into());
+            }
+            return Err(MatmulError::Format {
+                format,
+                message: format!("expected {len} elements, the input ends before"),
+            });
+        }
+    }
+
+    Ok(data)
+}
+
+/// Fails if `file` is longer than `len` bytes, the mapped counterpart of [expect_end]
+fn expect_len(file: &fs::File, len: u64, format: &'static str) -> Result<()> {
+    if file.metadata()?.len() != len {
+        return Err(MatmulError::Format {
+            format,
+            message: "unexpected trailing input".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Fails if `reader` has anything left
+fn expect_end(reader: &mut impl Read, format: &'static str) -> Result<()> {
+    if reader.read(&mut [0])? != 0 {
+        return Err(MatmulError::Format {
+            format,
+            message: "unexpected trailing input".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Writes a matrix to a file as plain text, one row per line
+pub fn write_file<T: Element>(path: &Path, matrix: &Matrix<T>) -> Result<()> {
+    write_file_as(path, matrix, FileFormat::Text)
+}
+
+/// Writes both operands to a file in `format`, so that [parse_file] reads them back
+///
+/// Text files get a `n m k` header, binary ones hold the matrices one after another and NumPy ones
+/// are a `.npz` with arrays `a` and `b`. Matrix Market and CSV files only hold a single matrix
+pub fn write_operands<T: Element>(
+    path: &Path,
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+    format: FileFormat,
+) -> Result<()> {
+    if a.cols != b.rows {
+        return Err(MatmulError::DimensionMismatch {
+            left: (a.rows, a.cols),
+            right: (b.rows, b.cols),
+        });
+    }
+
+    let single = match format {
+        FileFormat::Mtx => Some("Matrix Market"),
+        FileFormat::Csv => Some("csv"),
+        FileFormat::Text | FileFormat::Binary | FileFormat::Npy => None,
+    };
+    if let Some(format) = single {
+        return Err(MatmulError::Format {
+            format,
+            message: "file holds a single matrix, both operands can not be written to it"
+                .to_string(),
+        });
+    }
+
+    let file = fs::File::create(path)?;
+    let mut writer = io::BufWriter::new(file);
+
+    match format {
+        FileFormat::Text => text::write_operands(&mut writer, a, b)?,
+        FileFormat::Binary => {
+            binary::write(&mut writer, a)?;
+            binary::write(&mut writer, b)?;
+        }
+        FileFormat::Npy => npy::write_npz(&mut writer, a, b)?,
+        // checked above
+        FileFormat::Mtx | FileFormat::Csv => unreachable!(),
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes a matrix to a file in `format`
+pub fn write_file_as<T: Element>(
+    path: &Path,
+    matrix: &Matrix<T>,
+    format: FileFormat,
+) -> Result<()> {
+    let file = fs::File::create(path)?;
+    let mut writer = io::BufWriter::new(file);
+
+    match format {
+        FileFormat::Text => text::write(&mut writer, matrix)?,
+        FileFormat::Binary => binary::write(&mut writer, matrix)?,
+        FileFormat::Npy => npy::write(&mut writer, matrix)?,
+        FileFormat::Mtx => mtx::write(&mut writer, matrix)?,
+        FileFormat::Csv => csv::write(&mut writer, matrix, ',')?,
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,317 @@
+use std::io::{BufRead, Write};
+
+use crate::Element;
+use crate::ElementType;
+use crate::MatmulError;
+use crate::Matrix;
+use crate::Result;
+use crate::SparseMatrix;
+
+/// First bytes of every Matrix Market file
+pub const MAGIC: &[u8] = b"%%MatrixMarket";
+
+/// Whether `bytes` start like a Matrix Market file
+pub fn is_mtx(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Layout {
+    /// Every element, column by column
+    Array,
+    /// `row col value` triples of the non zero elements
+    Coordinate,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Field {
+    Real,
+    Integer,
+    /// Only positions are stored, every value is one
+    Pattern,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Symmetry {
+    General,
+    /// Only the lower triangle is stored, `a(i, j) = a(j, i)`
+    Symmetric,
+    /// Only the strictly lower triangle is stored, `a(i, j) = -a(j, i)`
+    SkewSymmetric,
+}
+
+/// Reads lines of a Matrix Market file, skipping comments and blank lines
+struct Lines<'a, R> {
+    reader: &'a mut R,
+    buf: String,
+    line_no: usize,
+}
+
+impl<R: BufRead> Lines<'_, R> {
+    /// Next line with data and its number, `None` at the end of the file
+    fn next(&mut self) -> Result<Option<(&str, usize)>> {
+        loop {
+            self.buf.clear();
+            if self.reader.read_line(&mut self.buf)? == 0 {
+                return Ok(None);
+            }
+            self.line_no += 1;
+
+            let line = self.buf.trim();
+            if !line.is_empty() && !line.starts_with('%') {
+                return Ok(Some((self.buf.trim(), self.line_no)));
+            }
+        }
+    }
+
+    /// Next line with data, `what` describes it in the error if the file ends
+    fn expect(&mut self, what: &str) -> Result<(&str, usize)> {
+        let line_no = self.line_no + 1;
+        self.next()?.ok_or_else(|| MatmulError::Parse {
+            line: line_no,
+            column: 1,
+            message: format!("unexpected end of file, expected {what}"),
+        })
+    }
+}
+
+/// Whitespace separated tokens of `line` with their columns, there must be exactly `count` of them
+fn tokens(line: &str, line_no: usize, count: usize) -> Result<Vec<(usize, &str)>> {
+    let res = super::tokens(line);
+
+    if res.len() != count {
+        return Err(super::count_error(line, line_no, &res, count));
+    }
+
+    Ok(res)
+}
+
+/// Reads a Matrix Market file in either layout as a sparse matrix
+///
+/// Real, integer and pattern fields are supported, with general, symmetric or skew symmetric
+/// symmetry. Values are parsed as `T`, so real values can not be read as integers
+pub fn read_sparse<T: Element>(reader: &mut impl BufRead) -> Result<SparseMatrix<T>> {
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+
+    let (layout, field, symmetry) = parse_header(&header)?;
+
+    let mut lines = Lines {
+        reader,
+        buf: String::new(),
+        line_no: 1,
+    };
+
+    let (size, line_no) = lines.expect("the size line")?;
+    let counts = match layout {
+        Layout::Array => 2,
+        Layout::Coordinate => 3,
+    };
+    let size = tokens(size, line_no, counts)?
+        .into_iter()
+        .map(|token| super::parse_token::<usize>(token, line_no))
+        .collect::<Result<Vec<_>>>()?;
+    let (rows, cols) = (size[0], size[1]);
+
+    if symmetry != Symmetry::General && rows != cols {
+        return Err(format_error(format!(
+            "symmetric matrix is not square, {rows}x{cols}"
+        )));
+    }
+
+    let mut entries = vec![];
+
+    match layout {
+        Layout::Coordinate => {
+            for _ in 0..size[2] {
+                let (line, line_no) = lines.expect("an entry")?;
+                let entry = parse_entry(line, line_no, field, rows, cols)?;
+                entries.push(entry);
+            }
+        }
+        Layout::Array => {
+            // column by column, symmetric matrices only store the lower triangle
+            for col in 0..cols {
+                let first_row = match symmetry {
+                    Symmetry::General => 0,
+                    Symmetry::Symmetric => col,
+                    Symmetry::SkewSymmetric => col + 1,
+                };
+                for row in first_row..rows {
+                    let (line, line_no) = lines.expect("a value")?;
+                    let value = super::parse_token(tokens(line, line_no, 1)?[0], line_no)?;
+                    entries.push((row, col, value));
+                }
+            }
+        }
+    }
+
+    if let Some((_, line_no)) = lines.next()? {
+        return Err(MatmulError::Parse {
+            line: line_no,
+            column: 1,
+            message: "unexpected trailing input".to_string(),
+        });
+    }
+
+    // mirror the stored triangle
+    let mirrored = entries
+        .iter()
+        .filter(|(row, col, _)| row != col)
+        .filter_map(|&(row, col, value)| match symmetry {
+            Symmetry::General => None,
+            Symmetry::Symmetric => Some((col, row, value)),
+            Symmetry::SkewSymmetric => Some((col, row, T::default().wrapping_sub(value))),
+        })
+        .collect::<Vec<_>>();
+    entries.extend(mirrored);
+
+    Ok(SparseMatrix {
+        rows,
+        cols,
+        entries,
+    })
+}
+
+/// Reads a Matrix Market file in either layout as a dense matrix, see [read_sparse]
+///
+/// Fails if the declared shape has more elements than memory can be addressed for, sparse files
+/// can declare far larger matrices than they hold
+pub fn read<T: Element>(reader: &mut impl BufRead) -> Result<Matrix<T>> {
+    let sparse = read_sparse(reader)?;
+    let (rows, cols) = (sparse.rows, sparse.cols);
+
+    let size = rows
+        .checked_mul(cols)
+        .and_then(|len| len.checked_mul(std::mem::size_of::<T>()));
+    if size.is_none_or(|size| size > isize::MAX as usize) {
+        return Err(format_error(format!(
+            "{rows}x{cols} matrix is too large to be dense"
+        )));
+    }
+
+    Ok(sparse.to_dense())
+}
+
+/// Writes a dense matrix in the array layout
+pub fn write<T: Element>(writer: &mut impl Write, matrix: &Matrix<T>) -> Result<()> {
+    writeln!(
+        writer,
+        "%%MatrixMarket matrix array {} general",
+        field_name::<T>()
+    )?;
+    writeln!(writer, "{} {}", matrix.rows, matrix.cols)?;
+
+    for col in 0..matrix.cols {
+        for row in 0..matrix.rows {
+            writeln!(writer, "{}", matrix.get(row, col))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a sparse matrix in the coordinate layout
+pub fn write_sparse<T: Element>(writer: &mut impl Write, matrix: &SparseMatrix<T>) -> Result<()> {
+    writeln!(
+        writer,
+        "%%MatrixMarket matrix coordinate {} general",
+        field_name::<T>()
+    )?;
+    writeln!(
+        writer,
+        "{} {} {}",
+        matrix.rows,
+        matrix.cols,
+        matrix.entries.len()
+    )?;
+
+    for (row, col, value) in &matrix.entries {
+        writeln!(writer, "{} {} {}", row + 1, col + 1, value)?;
+    }
+
+    Ok(())
+}
+
+fn field_name<T: Element>() -> &'static str {
+    match T::TYPE {
+        ElementType::I32 | ElementType::I64 => "integer",
+        _ => "real",
+    }
+}
+
+/// Parses `%%MatrixMarket matrix <layout> <field> <symmetry>`
+fn parse_header(header: &str) -> Result<(Layout, Field, Symmetry)> {
+    let header = header.trim().to_lowercase();
+    let tokens = header.split_whitespace().collect::<Vec<_>>();
+
+    let [magic, object, layout, field, symmetry] = tokens[..] else {
+        return Err(format_error(format!("bad header `{header}`")));
+    };
+
+    if magic != "%%matrixmarket" || object != "matrix" {
+        return Err(format_error(format!("bad header `{header}`")));
+    }
+
+    let layout = match layout {
+        "array" => Layout::Array,
+        "coordinate" => Layout::Coordinate,
+        layout => return Err(format_error(format!("unsupported layout `{layout}`"))),
+    };
+
+    let field = match field {
+        "real" | "double" => Field::Real,
+        "integer" => Field::Integer,
+        "pattern" if layout == Layout::Coordinate => Field::Pattern,
+        field => return Err(format_error(format!("unsupported field `{field}`"))),
+    };
+
+    let symmetry = match symmetry {
+        "general" => Symmetry::General,
+        "symmetric" => Symmetry::Symmetric,
+        "skew-symmetric" => Symmetry::SkewSymmetric,
+        symmetry => return Err(format_error(format!("unsupported symmetry `{symmetry}`"))),
+    };
+
+    Ok((layout, field, symmetry))
+}
+
+/// Parses a `row col [value]` line of the coordinate layout, indices start at 1 in the file
+fn parse_entry<T: Element>(
+    line: &str,
+    line_no: usize,
+    field: Field,
+    rows: usize,
+    cols: usize,
+) -> Result<(usize, usize, T)> {
+    let count = match field {
+        Field::Pattern => 2,
+        Field::Real | Field::Integer => 3,
+    };
+    let tokens = tokens(line, line_no, count)?;
+
+    let row = super::parse_token::<usize>(tokens[0], line_no)?;
+    let col = super::parse_token::<usize>(tokens[1], line_no)?;
+
+    if !(1..=rows).contains(&row) || !(1..=cols).contains(&col) {
+        return Err(MatmulError::Parse {
+            line: line_no,
+            column: tokens[0].0,
+            message: format!("entry ({row}, {col}) is outside of the {rows}x{cols} matrix"),
+        });
+    }
+
+    let value = match tokens.get(2) {
+        Some(&token) => super::parse_token(token, line_no)?,
+        None => T::from_f64(1.0),
+    };
+
+    Ok((row - 1, col - 1, value))
+}
+
+fn format_error(message: impl Into<String>) -> MatmulError {
+    MatmulError::Format {
+        format: "Matrix Market",
+        message: message.into(),
+    }
+}
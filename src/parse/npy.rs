@@ -0,0 +1,275 @@
+use std::io::{Read, Seek, Write};
+
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::element::{as_bytes, as_bytes_mut};
+use crate::Element;
+use crate::ElementType;
+use crate::MatmulError;
+use crate::Matrix;
+use crate::Result;
+
+/// First bytes of every `.npy` file
+pub const MAGIC: &[u8] = b"\x93NUMPY";
+/// First bytes of every `.npz` file, which is a zip archive of `.npy` files
+pub const NPZ_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Names the two operands can be stored under in a `.npz`, as written by `np.savez(f, a=a, b=b)`
+/// or `np.savez(f, a, b)`
+const NPZ_NAMES: [(&str, &str); 3] = [("a", "b"), ("A", "B"), ("arr_0", "arr_1")];
+
+/// Whether `bytes` start like a `.npy` file
+pub fn is_npy(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Whether `bytes` start like a `.npz` file
+pub fn is_npz(bytes: &[u8]) -> bool {
+    bytes.starts_with(NPZ_MAGIC)
+}
+
+/// NumPy type descriptor of `ty` without the byte order, `None` if NumPy has no such type
+fn descr(ty: ElementType) -> Option<&'static str> {
+    match ty {
+        ElementType::F32 => Some("f4"),
+        ElementType::F64 => Some("f8"),
+        ElementType::I32 => Some("i4"),
+        ElementType::I64 => Some("i8"),
+        ElementType::F16 => Some("f2"),
+        ElementType::Bf16 => None,
+    }
+}
+
+/// Reads a two dimensional array from a `.npy` file, both C and Fortran order are supported
+///
+/// The element type of the array must be the NumPy equivalent of `T`
+pub fn read<T: Element>(reader: &mut impl Read) -> Result<Matrix<T>> {
+    let mut preamble = [0; 8];
+    reader.read_exact(&mut preamble)?;
+
+    if !is_npy(&preamble) {
+        return Err(format_error("bad magic bytes"));
+    }
+
+    let header_len = match preamble[6] {
+        1 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        major => return Err(format_error(format!("unsupported version {major}"))),
+    };
+
+    let mut header = vec![0; header_len];
+    reader.read_exact(&mut header)?;
+    let header = String::from_utf8_lossy(&header);
+    let header = Header::parse(&header)?;
+
+    let Some(expected) = descr(T::TYPE) else {
+        let msg = format!("{} has no NumPy equivalent", T::NAME);
+        return Err(format_error(msg));
+    };
+
+    // single byte types have no byte order, `|`
+    let (order, descr) = header.descr.split_at_checked(1).unwrap_or_default();
+    if descr != expected || !matches!(order, "<" | ">" | "|" | "=") {
+        let msg = format!("elements are `{}`, expected `<{expected}`", header.descr);
+        return Err(format_error(msg));
+    }
+
+    let (rows, cols) = header.shape;
+    let Some(len) = rows.checked_mul(cols) else {
+        return Err(format_error(format!("{rows}x{cols} array is too large")));
+    };
+
+    let mut data = super::read_elements::<T>(reader, len, "npy")?;
+
+    let big_endian = order == ">" || (order == "=" && cfg!(target_endian = "big"));
+    if big_endian != cfg!(target_endian = "big") {
+        as_bytes_mut(&mut data)
+            .chunks_mut(std::mem::size_of::<T>())
+            .for_each(|el| el.reverse());
+    }
+
+    if !header.fortran_order {
+        return Matrix::from_vec(rows, cols, data);
+    }
+
+    // column major, element `(i, j)` is at `j * rows + i`
+    let mut res = Matrix::create_empty(rows, cols);
+    for i in 0..rows {
+        for j in 0..cols {
+            res.set(i, j, data[j * rows + i]);
+        }
+    }
+
+    Ok(res)
+}
+
+/// Writes a matrix as a version 1.0 `.npy` file in C order
+///
+/// May fail if NumPy has no equivalent of `T`
+pub fn write<T: Element>(writer: &mut impl Write, matrix: &Matrix<T>) -> Result<()> {
+    let Some(descr) = descr(T::TYPE) else {
+        let msg = format!("{} has no NumPy equivalent", T::NAME);
+        return Err(format_error(msg));
+    };
+
+    let endian = if cfg!(target_endian = "big") {
+        '>'
+    } else {
+        '<'
+    };
+    let mut header = format!(
+        "{{'descr': '{endian}{descr}', 'fortran_order': False, 'shape': ({}, {}), }}",
+        matrix.rows, matrix.cols
+    );
+
+    // data is aligned to 64 bytes, the header ends with a newline
+    let unpadded = MAGIC.len() + 2 + 2 + header.len() + 1;
+    header += &" ".repeat(unpadded.next_multiple_of(64) - unpadded);
+    header.push('\n');
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    writer.write_all(as_bytes(&matrix.data))?;
+
+    Ok(())
+}
+
+/// Reads the two operands from a `.npz` file
+///
+/// They must be stored as `a` and `b`, `A` and `B` or `arr_0` and `arr_1`
+pub fn read_npz<T: Element>(reader: impl Read + Seek) -> Result<(Matrix<T>, Matrix<T>)> {
+    let mut archive = ZipArchive::new(reader).map_err(npz_error)?;
+
+    let names = NPZ_NAMES.into_iter().find(|(a, b)| {
+        let names = archive.file_names().collect::<Vec<_>>();
+        names.contains(&format!("{a}.npy").as_str()) && names.contains(&format!("{b}.npy").as_str())
+    });
+
+    let Some((a, b)) = names else {
+        let msg = "expected arrays named `a` and `b`, `A` and `B` or `arr_0` and `arr_1`";
+        return Err(MatmulError::Format {
+            format: "npz",
+            message: msg.to_string(),
+        });
+    };
+
+    let m1 = read(&mut archive.by_name(&format!("{a}.npy")).map_err(npz_error)?)?;
+    let m2 = read(&mut archive.by_name(&format!("{b}.npy")).map_err(npz_error)?)?;
+
+    Ok((m1, m2))
+}
+
+/// Writes both operands to a `.npz` as `a` and `b`, uncompressed like `np.savez` does
+pub fn write_npz<T: Element>(
+    writer: impl Write + Seek,
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+) -> Result<()> {
+    let mut archive = ZipWriter::new(writer);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    let (name_a, name_b) = NPZ_NAMES[0];
+    for (name, matrix) in [(name_a, a), (name_b, b)] {
+        archive
+            .start_file(format!("{name}.npy"), options)
+            .map_err(npz_error)?;
+        write(&mut archive, matrix)?;
+    }
+
+    archive.finish().map_err(npz_error)?;
+
+    Ok(())
+}
+
+/// Fields of a `.npy` header that matter for two dimensional arrays
+struct Header {
+    descr: String,
+    fortran_order: bool,
+    shape: (usize, usize),
+}
+
+impl Header {
+    /// Parses the python dict literal, for example
+    /// `{'descr': '<f4', 'fortran_order': False, 'shape': (3, 4), }`
+    fn parse(header: &str) -> Result<Self> {
+        let descr = value(header, "descr")?;
+        let descr = descr
+            .split(['\'', '"'])
+            .nth(1)
+            .ok_or_else(|| format_error(format!("bad descr `{descr}`")))?
+            .to_string();
+
+        let fortran_order = match value(header, "fortran_order")? {
+            order if order.starts_with("True") => true,
+            order if order.starts_with("False") => false,
+            order => return Err(format_error(format!("bad fortran_order `{order}`"))),
+        };
+
+        let shape = value(header, "shape")?;
+        let dims = shape
+            .strip_prefix('(')
+            .and_then(|shape| shape.split(')').next())
+            .ok_or_else(|| format_error(format!("bad shape `{shape}`")))?
+            .split(',')
+            .map(str::trim)
+            .filter(|dim| !dim.is_empty())
+            .map(|dim| dim.trim_end_matches('L').parse::<usize>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| format_error(format!("bad shape `{shape}`, {e}")))?;
+
+        let [rows, cols] = dims[..] else {
+            let msg = format!("expected a two dimensional array, got shape {dims:?}");
+            return Err(format_error(msg));
+        };
+
+        Ok(Self {
+            descr,
+            fortran_order,
+            shape: (rows, cols),
+        })
+    }
+}
+
+/// Text right after `'key':` in the header
+fn value<'a>(header: &'a str, key: &str) -> Result<&'a str> {
+    let start = [format!("'{key}'"), format!("\"{key}\"")]
+        .iter()
+        .find_map(|key| header.find(key.as_str()).map(|start| start + key.len()))
+        .ok_or_else(|| format_error(format!("header has no `{key}`")))?;
+
+    let value = header[start..].trim_start();
+    let value = value
+        .strip_prefix(':')
+        .ok_or_else(|| format_error(format!("header has no value for `{key}`")))?;
+
+    Ok(value.trim_start())
+}
+
+fn format_error(message: impl Into<String>) -> MatmulError {
+    MatmulError::Format {
+        format: "npy",
+        message: message.into(),
+    }
+}
+
+fn npz_error(error: ZipError) -> MatmulError {
+    match error {
+        ZipError::Io(e) => e.into(),
+        e => MatmulError::Format {
+            format: "npz",
+            message: e.to_string(),
+        },
+    }
+}
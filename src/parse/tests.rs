@@ -0,0 +1,334 @@
+use std::path::PathBuf;
+
+use crate::{FileFormat, Matrix};
+
+// Error of a function reading a file, without the path that is attached to it
+fn file_error<T>(res: crate::Result<T>) -> crate::MatmulError {
+    match res {
+        Err(crate::MatmulError::File { source, .. }) => *source,
+        _ => panic!("expected an error in a file"),
+    }
+}
+
+// Path of a file in the temporary directory that no other test uses
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("matmul-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn test_binary_roundtrip() {
+    let m1 = Matrix::create(2, 3, &[1.5f64, -2.0, 3.25, 4.0, 5.0, f64::MAX]).unwrap();
+    let m2 = Matrix::create(3, 1, &[1e-300f64, 0.0, -0.0]).unwrap();
+
+    let mut bytes = vec![];
+    crate::write_binary(&mut bytes, &m1).unwrap();
+    crate::write_binary(&mut bytes, &m2).unwrap();
+    assert_eq!(bytes.len(), 2 * 24 + 9 * 8);
+
+    let path = temp_path("roundtrip.bin");
+    std::fs::write(&path, &bytes).unwrap();
+    let (r1, r2) = crate::parse_file::<f64>(&path).unwrap();
+
+    // bit exact, `PartialEq` would also accept -0.0 for 0.0
+    assert_eq!((r1.rows, r1.cols, r2.rows, r2.cols), (2, 3, 3, 1));
+    assert_eq!(r1.data.iter().map(|el| el.to_bits()).collect::<Vec<_>>(), m1.data.iter().map(|el| el.to_bits()).collect::<Vec<_>>());
+    assert_eq!(r2.data.iter().map(|el| el.to_bits()).collect::<Vec<_>>(), m2.data.iter().map(|el| el.to_bits()).collect::<Vec<_>>());
+
+    // elements of another type are rejected, so is trailing input
+    assert!(matches!(file_error(crate::parse_file::<f32>(&path)), crate::MatmulError::Format { .. }));
+    bytes.push(0);
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(matches!(file_error(crate::parse_file::<f64>(&path)), crate::MatmulError::Format { .. }));
+
+    // more rows than the input holds is an error, not an allocation of all of them
+    bytes[8..16].copy_from_slice(&(1u64 << 40).to_le_bytes());
+    assert!(matches!(crate::read_binary::<f64>(&mut bytes.as_slice()), Err(crate::MatmulError::Format { .. })));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_write_formats() {
+    let m = Matrix::create(2, 2, &[1, 2, 3, 4]).unwrap();
+
+    let path = temp_path("formats.bin");
+    crate::write_file_as(&path, &m, FileFormat::Binary).unwrap();
+    let res = crate::read_binary::<i32>(&mut std::fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!(res, m);
+
+    crate::write_file_as(&path, &m, FileFormat::Text).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), m.to_string());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_write_operands() {
+    use crate::{Distribution, Generator};
+
+    let mut generator = Generator::new(1);
+    let a = generator.matrix::<f64>(3, 4, Distribution::Normal { mean: 0.0, std_dev: 1.0 });
+    let b = generator.matrix::<f64>(4, 2, Distribution::Sparse { density: 0.5 });
+
+    // every format that holds both operands is read back by `parse_file`
+    for (name, format) in [("operands.txt", FileFormat::Text), ("operands.bin", FileFormat::Binary), ("operands.npz", FileFormat::Npy)] {
+        let path = temp_path(name);
+        crate::write_operands(&path, &a, &b, format).unwrap();
+        let (r1, r2) = crate::parse_file::<f64>(&path).unwrap();
+        assert_eq!((&r1, &r2), (&a, &b));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    let path = temp_path("operands.csv");
+    assert!(matches!(crate::write_operands(&path, &a, &b, FileFormat::Csv), Err(crate::MatmulError::Format { .. })));
+    assert!(matches!(crate::write_operands(&path, &b, &a, FileFormat::Text), Err(crate::MatmulError::DimensionMismatch { .. })));
+    assert!(!path.exists());
+}
+
+// `.npy` file with the header `header` followed by `data`, laid out the way `np.save` does
+fn npy_bytes(header: &str, data: &[u8]) -> Vec<u8> {
+    let mut header = header.to_string();
+    while !(10 + header.len() + 1).is_multiple_of(64) {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    let mut res = b"\x93NUMPY\x01\x00".to_vec();
+    res.extend((header.len() as u16).to_le_bytes());
+    res.extend(header.as_bytes());
+    res.extend(data);
+    res
+}
+
+#[test]
+fn test_npy() {
+    let m = Matrix::create(2, 3, &[1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+
+    let mut bytes = vec![];
+    crate::write_npy(&mut bytes, &m).unwrap();
+    assert_eq!(bytes.len() % 64, 6 * 4);
+    assert_eq!(crate::read_npy::<f32>(&mut bytes.as_slice()).unwrap(), m);
+
+    // column major and big endian
+    let data = [1.0f64, 4.0, 2.0, 5.0, 3.0, 6.0].iter().flat_map(|el| el.to_be_bytes()).collect::<Vec<_>>();
+    let bytes = npy_bytes("{'descr': '>f8', 'fortran_order': True, 'shape': (2, 3), }", &data);
+    assert_eq!(crate::read_npy::<f64>(&mut bytes.as_slice()).unwrap(), m.convert::<f64>());
+
+    // wrong element type and wrong number of dimensions
+    let bytes = npy_bytes("{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }", &data);
+    assert!(matches!(crate::read_npy::<f32>(&mut bytes.as_slice()), Err(crate::MatmulError::Format { .. })));
+    let bytes = npy_bytes("{'descr': '<f8', 'fortran_order': False, 'shape': (6,), }", &data);
+    assert!(matches!(crate::read_npy::<f64>(&mut bytes.as_slice()), Err(crate::MatmulError::Format { .. })));
+
+    // a shape larger than the data is an error, not an allocation of the whole shape
+    let bytes = npy_bytes("{'descr': '<f8', 'fortran_order': False, 'shape': (1099511627776, 1048576), }", &data);
+    assert!(matches!(crate::read_npy::<f64>(&mut bytes.as_slice()), Err(crate::MatmulError::Format { .. })));
+
+    // bf16 has no NumPy equivalent
+    assert!(crate::write_npy(&mut vec![], &m.convert::<crate::bf16>()).is_err());
+}
+
+#[test]
+fn test_npz() {
+    use std::io::Write;
+
+    let m1 = Matrix::create(2, 3, &[1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let m2 = Matrix::create(3, 1, &[1.0f64, 2.0, 3.0]).unwrap();
+
+    let path = temp_path("operands.npz");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    for (name, m) in [("arr_1.npy", &m2), ("arr_0.npy", &m1)] {
+        zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+        let mut bytes = vec![];
+        crate::write_npy(&mut bytes, m).unwrap();
+        zip.write_all(&bytes).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let (r1, r2) = crate::parse_file::<f64>(&path).unwrap();
+    assert_eq!((r1, r2), (m1, m2));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_mtx_coordinate() {
+    let general = "%%MatrixMarket matrix coordinate real general\n% comment\n\n2 3 3\n1 1 1.5\n2 3 -2\n1 1 0.5\n";
+    let res = crate::read_mtx::<f64>(&mut general.as_bytes()).unwrap();
+    assert_eq!(res, Matrix::create(2, 3, &[2.0, 0.0, 0.0, 0.0, 0.0, -2.0]).unwrap());
+
+    let symmetric = "%%MatrixMarket matrix coordinate integer symmetric\n3 3 3\n1 1 1\n3 1 2\n3 2 3\n";
+    let res = crate::read_mtx::<i32>(&mut symmetric.as_bytes()).unwrap();
+    assert_eq!(res, Matrix::create(3, 3, &[1, 0, 2, 0, 0, 3, 2, 3, 0]).unwrap());
+
+    let pattern = "%%MatrixMarket matrix coordinate pattern skew-symmetric\n2 2 1\n2 1\n";
+    let res = crate::read_mtx_sparse::<f32>(&mut pattern.as_bytes()).unwrap();
+    assert_eq!(res.to_dense(), Matrix::create(2, 2, &[0.0, -1.0, 1.0, 0.0]).unwrap());
+
+    // out of bounds entry, bad value, missing entry
+    let bad = "%%MatrixMarket matrix coordinate real general\n2 2 1\n3 1 1.0\n";
+    assert!(matches!(crate::read_mtx::<f32>(&mut bad.as_bytes()), Err(crate::MatmulError::Parse { line: 3, column: 1, .. })));
+    let bad = "%%MatrixMarket matrix coordinate real general\n2 2 1\n1 1 x\n";
+    assert!(matches!(crate::read_mtx::<f32>(&mut bad.as_bytes()), Err(crate::MatmulError::Parse { line: 3, column: 5, .. })));
+    let bad = "%%MatrixMarket matrix coordinate real general\n2 2 2\n1 1 1.0\n";
+    assert!(matches!(crate::read_mtx::<f32>(&mut bad.as_bytes()), Err(crate::MatmulError::Parse { line: 4, .. })));
+
+    // a shape that can not be dense, though the sparse matrix is tiny
+    let huge = "%%MatrixMarket matrix coordinate real general\n4294967296 4294967296 1\n1 1 1.0\n";
+    assert!(matches!(crate::read_mtx::<f32>(&mut huge.as_bytes()), Err(crate::MatmulError::Format { .. })));
+    assert_eq!(crate::read_mtx_sparse::<f32>(&mut huge.as_bytes()).unwrap().entries.len(), 1);
+}
+
+#[test]
+fn test_mtx_array() {
+    let symmetric = "%%MatrixMarket matrix array real symmetric\n2 2\n1\n2\n3\n";
+    let res = crate::read_mtx::<f32>(&mut symmetric.as_bytes()).unwrap();
+    assert_eq!(res, Matrix::create(2, 2, &[1.0, 2.0, 2.0, 3.0]).unwrap());
+
+    // trailing values
+    let bad = "%%MatrixMarket matrix array real general\n1 1\n1\n2\n";
+    assert!(matches!(crate::read_mtx::<f32>(&mut bad.as_bytes()), Err(crate::MatmulError::Parse { line: 4, .. })));
+
+    let m = Matrix::create(2, 3, &[1, 0, 3, 4, 5, 0]).unwrap();
+    let path = temp_path("matrix.mtx");
+    crate::write_file_as(&path, &m, FileFormat::Mtx).unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(text.starts_with("%%MatrixMarket matrix array integer general\n2 3\n1\n4\n"));
+    assert_eq!(crate::read_mtx::<i32>(&mut text.as_bytes()).unwrap(), m);
+
+    // a single matrix is not a valid input file
+    assert!(matches!(file_error(crate::parse_file::<i32>(&path)), crate::MatmulError::Format { .. }));
+
+    let mut bytes = vec![];
+    crate::write_mtx_sparse(&mut bytes, &crate::SparseMatrix::from_dense(&m)).unwrap();
+    assert_eq!(String::from_utf8(bytes.clone()).unwrap(), "%%MatrixMarket matrix coordinate integer general\n2 3 4\n1 1 1\n1 3 3\n2 1 4\n2 2 5\n");
+    assert_eq!(crate::read_mtx::<i32>(&mut bytes.as_slice()).unwrap(), m);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_csv() {
+    use crate::CsvOptions;
+
+    let m = Matrix::create(2, 3, &[1.0f32, 2.5, -3.0, 4.0, 5.0, 6.0]).unwrap();
+
+    // header and delimiter are detected, quotes and blank lines are skipped
+    for csv in ["x,y,z\n1,2.5,-3\n\n4, 5, 6\n", "\"x\"\t\"y\"\t\"z\"\n\"1\"\t\"2.5\"\t\"-3\"\n4\t5\t6\n", "1;2.5;-3\r\n4;5;6\r\n", "1  2.5 -3\n4 5 6\n"] {
+        assert_eq!(crate::read_csv::<f32>(&mut csv.as_bytes(), &CsvOptions::default()).unwrap(), m);
+    }
+
+    // given options win over detection
+    let options = CsvOptions { delimiter: Some('|'), header: Some(true) };
+    assert_eq!(crate::read_csv::<f32>(&mut "1|2|3\n1|2.5|-3\n4|5|6\n".as_bytes(), &options).unwrap(), m);
+
+    // ragged rows, bad values and empty files
+    let options = CsvOptions::default();
+    assert!(matches!(crate::read_csv::<f32>(&mut "1,2\n3\n".as_bytes(), &options), Err(crate::MatmulError::Parse { line: 2, .. })));
+    assert!(matches!(crate::read_csv::<i32>(&mut "1,2\n3,4.5\n".as_bytes(), &options), Err(crate::MatmulError::Parse { line: 2, column: 3, .. })));
+    assert!(matches!(crate::read_csv::<f32>(&mut "a,b\n".as_bytes(), &options), Err(crate::MatmulError::Format { .. })));
+
+    let path = temp_path("matrix.csv");
+    crate::write_file_as(&path, &m, FileFormat::Csv).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "1,2.5,-3\n4,5,6\n");
+    assert_eq!(crate::parse_matrix_file::<f32>(&path, &options).unwrap(), m);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_parse_matrix_file() {
+    let m = Matrix::create(2, 2, &[1i64, 2, 3, 4]).unwrap();
+    let options = crate::CsvOptions::default();
+
+    let path = temp_path("single.bin");
+    for format in [FileFormat::Binary, FileFormat::Npy, FileFormat::Mtx, FileFormat::Text] {
+        crate::write_file_as(&path, &m, format).unwrap();
+        assert_eq!(crate::parse_matrix_file::<i64>(&path, &options).unwrap(), m);
+    }
+
+    // a file with both operands is not a single matrix
+    let mut bytes = vec![];
+    crate::write_binary(&mut bytes, &m).unwrap();
+    crate::write_binary(&mut bytes, &m).unwrap();
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(matches!(file_error(crate::parse_matrix_file::<i64>(&path, &options)), crate::MatmulError::Format { .. }));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_binary_mapped() {
+    let m1 = Matrix::create(2, 3, &[1i64, 2, 3, 4, 5, 6]).unwrap();
+    let m2 = Matrix::create(3, 1, &[7i64, 8, 9]).unwrap();
+
+    let mut bytes = vec![];
+    crate::write_binary(&mut bytes, &m1).unwrap();
+    crate::write_binary(&mut bytes, &m2).unwrap();
+
+    let path = temp_path("mapped.bin");
+    std::fs::write(&path, &bytes).unwrap();
+
+    // the safe functions read the file
+    let (r1, r2) = crate::parse_file::<i64>(&path).unwrap();
+    assert_eq!((&r1, &r2), (&m1, &m2));
+    assert!(!r1.data.is_mapped() && !r2.data.is_mapped());
+    assert!(!crate::parse_matrix_file::<i64>(&path, &Default::default()).is_ok_and(|r| r.data.is_mapped()));
+
+    // the file is not modified while the matrices are alive
+    let (mut r1, r2) = unsafe { crate::parse_file_mapped::<i64>(&path).unwrap() };
+    assert_eq!((&r1, &r2), (&m1, &m2));
+    assert_eq!(r1.data.is_mapped(), cfg!(target_endian = "little"));
+
+    // mapped elements are copy on write
+    r1.set(0, 0, 42);
+    assert_eq!(r1.get(0, 0), 42);
+    assert_eq!(std::fs::read(&path).unwrap(), bytes);
+    drop((r1, r2));
+
+    // a truncated file is an error, not a mapping past its end
+    std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    assert!(crate::parse_file::<i64>(&path).is_err());
+    assert!(crate::parse_matrix_file::<i64>(&path, &Default::default()).is_err());
+    assert!(unsafe { crate::parse_file_mapped::<i64>(&path) }.is_err());
+    assert!(unsafe { crate::parse_matrix_file_mapped::<i64>(&path, &Default::default()) }.is_err());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_text_whitespace() {
+    let text = "2 2  1\n\n1\t2 \n  3   4\r\n\n5\n6\n\n";
+    let (m1, m2) = crate::parse::text::read::<i32>(&mut text.as_bytes()).unwrap();
+    assert_eq!(m1, Matrix::create(2, 2, &[1, 2, 3, 4]).unwrap());
+    assert_eq!(m2, Matrix::create(2, 1, &[5, 6]).unwrap());
+}
+
+#[test]
+fn test_text_diagnostics() {
+    let path = temp_path("diagnostics.txt");
+
+    // too many values point at the first extra one, too few at the end of the line
+    for (text, line, column, token) in [
+        ("2 2 1\n1 2\n3 4 5\n6\n7\n", 3, 5, "`5`"),
+        ("2 2 1\n1 2\n\n3\n6\n7\n", 4, 2, "`3`"),
+        ("2 2 1\n1 2\n3 x\n6\n7\n", 3, 3, "`x`"),
+        ("2 2 1\n1 2\n3 4\n6\n", 5, 1, "end of file"),
+        ("2 2 1\n1 2\n3 4\n6\n7\n\n 8\n", 7, 2, "`8`"),
+        // headers are not trusted to allocate the matrices
+        ("\n4294967296 4294967296 1\n", 2, 1, "too large"),
+        ("100000 100000 1\n1 2\n", 2, 4, "`2`"),
+    ] {
+        std::fs::write(&path, text).unwrap();
+        let error = crate::parse_file::<f32>(&path).unwrap_err();
+        let message = error.to_string();
+
+        assert!(message.starts_with(&format!("parse error at {}:{line}:{column}, ", path.display())), "{message}");
+        assert!(message.contains(token), "{message}");
+    }
+
+    let error = crate::parse_file::<f32>(&temp_path("missing.txt")).unwrap_err();
+    assert!(error.to_string().starts_with(&temp_path("missing.txt").display().to_string()));
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,135 @@
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use crate::Element;
+use crate::MatmulError;
+use crate::Matrix;
+use crate::Result;
+
+/// Reads the next line that is not blank and parses exactly `count` values from it
+///
+/// Values are separated by any amount of whitespace. `line_no` is the number of the last line
+/// that was read, it is advanced past the parsed one
+fn read_row<T>(
+    reader: &mut impl BufRead,
+    buf: &mut String,
+    line_no: &mut usize,
+    count: usize,
+) -> Result<Vec<T>>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    // rows of an empty matrix are blank lines, which are skipped anyway
+    if count == 0 {
+        return Ok(vec![]);
+    }
+
+    loop {
+        buf.clear();
+        if reader.read_line(buf)? == 0 {
+            return Err(MatmulError::Parse {
+                line: *line_no + 1,
+                column: 1,
+                message: format!("unexpected end of file, expected a row of {count} values"),
+            });
+        }
+        *line_no += 1;
+
+        if !buf.trim().is_empty() {
+            break;
+        }
+    }
+
+    let tokens = super::tokens(buf);
+    if tokens.len() != count {
+        return Err(super::count_error(buf, *line_no, &tokens, count));
+    }
+
+    tokens
+        .into_iter()
+        .map(|token| super::parse_token(token, *line_no))
+        .collect()
+}
+
+/// Parses two matrices from text, a `n m k` header followed by `n` rows of `m` values and `m` rows of
+/// `k` values
+///
+/// Blank lines are skipped
+pub fn read<T: Element>(reader: &mut impl BufRead) -> Result<(Matrix<T>, Matrix<T>)> {
+    let mut buf = String::new();
+    let mut line_no = 0;
+
+    let dims = read_row::<usize>(reader, &mut buf, &mut line_no, 3)?;
+
+    let (n, m, k) = (dims[0], dims[1], dims[2]);
+
+    // the header is not trusted to allocate, the rows that follow have to fit in memory anyway
+    let fits = |rows: usize, cols: usize| {
+        rows.checked_mul(cols)
+            .and_then(|len| len.checked_mul(std::mem::size_of::<T>()))
+            .is_some_and(|size| size <= isize::MAX as usize)
+    };
+    if !fits(n, m) || !fits(m, k) {
+        return Err(MatmulError::Parse {
+            line: line_no,
+            column: 1,
+            message: format!("{n}x{m} by {m}x{k} matrices are too large"),
+        });
+    }
+
+    let mut data1 = vec![];
+    let mut data2 = vec![];
+
+    for _ in 0..n {
+        let mut nums = read_row(reader, &mut buf, &mut line_no, m)?;
+        data1.append(&mut nums)
+    }
+
+    for _ in 0..m {
+        let mut nums = read_row(reader, &mut buf, &mut line_no, k)?;
+        data2.append(&mut nums);
+    }
+
+    // assert only blank lines are left in the input file
+    loop {
+        buf.clear();
+        if reader.read_line(&mut buf)? == 0 {
+            break;
+        }
+        line_no += 1;
+
+        if let Some(&(column, token)) = super::tokens(&buf).first() {
+            return Err(MatmulError::Parse {
+                line: line_no,
+                column,
+                message: format!("unexpected trailing input `{token}`"),
+            });
+        }
+    }
+
+    let m1 = Matrix::from_vec(n, m, data1)?;
+    let m2 = Matrix::from_vec(m, k, data2)?;
+
+    Ok((m1, m2))
+}
+
+/// Writes a matrix as plain text, one row per line
+pub fn write<T: Element>(writer: &mut impl Write, matrix: &Matrix<T>) -> Result<()> {
+    write!(writer, "{matrix}")?;
+
+    Ok(())
+}
+
+/// Writes both operands in the format that [read] expects, a `n m k` header followed by the
+/// `n x m` and the `m x k` matrix
+pub fn write_operands<T: Element>(
+    writer: &mut impl Write,
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+) -> Result<()> {
+    writeln!(writer, "{} {} {}", a.rows, a.cols, b.cols)?;
+    write!(writer, "{a}{b}")?;
+
+    Ok(())
+}
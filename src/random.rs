@@ -0,0 +1,155 @@
+use std::f64::consts::PI;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::Element;
+use crate::Matrix;
+
+/// How the values of a generated matrix are distributed, see [Generator]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Distribution {
+    /// Uniform in `[low, high)`
+    Uniform { low: f64, high: f64 },
+    /// Normal with mean `mean` and standard deviation `std_dev`
+    Normal { mean: f64, std_dev: f64 },
+    /// Uniform integers in `[low, high]`
+    Integers { low: i64, high: i64 },
+    /// Ones on the diagonal, zeroes elsewhere
+    Identity,
+    /// Uniform in `[-1, 1)` on the diagonal, zeroes elsewhere
+    Diagonal,
+    /// Uniform in `[-1, 1)` at most `bandwidth` away from the diagonal, zeroes elsewhere
+    Banded { bandwidth: usize },
+    /// Uniform in `[-1, 1)` with probability `density`, zeroes elsewhere
+    Sparse { density: f64 },
+    /// Largest singular value is `condition` times the smallest one, the ones in between are
+    /// spaced geometrically
+    ///
+    /// The matrix is `U * S * V` with random Householder reflections `U` and `V`, so its
+    /// condition number is exactly `condition` up to rounding. Only useful for floats
+    IllConditioned { condition: f64 },
+}
+
+/// Source of random matrices, the same seed always gives the same matrices
+///
+/// ```
+/// use matmul::{Distribution, Generator, Matrix};
+///
+/// let normal = Distribution::Normal { mean: 0.0, std_dev: 1.0 };
+///
+/// let mut generator = Generator::new(42);
+/// let a: Matrix = generator.matrix(4, 3, normal);
+/// let b: Matrix = generator.matrix(3, 2, Distribution::Identity);
+///
+/// assert_eq!(Generator::new(42).matrix::<f32>(4, 3, normal), a);
+/// ```
+pub struct Generator {
+    rng: StdRng,
+}
+
+impl Generator {
+    /// Generator whose matrices only depend on `seed`
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// `rows x cols` matrix with values from `distribution`, converted to `T`
+    pub fn matrix<T: Element>(
+        &mut self,
+        rows: usize,
+        cols: usize,
+        distribution: Distribution,
+    ) -> Matrix<T> {
+        let values = match distribution {
+            Distribution::IllConditioned { condition } => {
+                self.ill_conditioned(rows, cols, condition)
+            }
+            distribution => (0..rows * cols)
+                .map(|idx| self.value(idx / cols.max(1), idx % cols.max(1), distribution))
+                .collect(),
+        };
+
+        let data = values.into_iter().map(T::from_f64).collect();
+        // unwrap is safe because there are `rows * cols` values
+        Matrix::from_vec(rows, cols, data).unwrap()
+    }
+
+    /// Value at `(row, col)` of any distribution but [Distribution::IllConditioned]
+    fn value(&mut self, row: usize, col: usize, distribution: Distribution) -> f64 {
+        match distribution {
+            Distribution::Uniform { low, high } => self.uniform(low, high),
+            Distribution::Normal { mean, std_dev } => mean + std_dev * self.normal(),
+            Distribution::Integers { low, high } => self.rng.gen_range(low..=high) as f64,
+            Distribution::Identity => (row == col) as u8 as f64,
+            Distribution::Diagonal if row == col => self.uniform(-1.0, 1.0),
+            Distribution::Banded { bandwidth } if row.abs_diff(col) <= bandwidth => {
+                self.uniform(-1.0, 1.0)
+            }
+            Distribution::Sparse { density } if self.rng.gen_bool(density.clamp(0.0, 1.0)) => {
+                self.uniform(-1.0, 1.0)
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn uniform(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.rng.gen::<f64>()
+    }
+
+    /// Standard normal value, with the Box-Muller transform
+    fn normal(&mut self) -> f64 {
+        // `1 - x` is in `(0, 1]`, so the logarithm is finite
+        let radius = (-2.0 * (1.0 - self.rng.gen::<f64>()).ln()).sqrt();
+        let angle = 2.0 * PI * self.rng.gen::<f64>();
+
+        radius * angle.cos()
+    }
+
+    /// Random unit vector of length `len`
+    fn unit_vector(&mut self, len: usize) -> Vec<f64> {
+        let vector = (0..len).map(|_| self.normal()).collect::<Vec<_>>();
+        let norm = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+
+        vector.into_iter().map(|x| x / norm).collect()
+    }
+
+    /// Row major values of `(I - 2uu^T) * S * (I - 2vv^T)`, see [Distribution::IllConditioned]
+    fn ill_conditioned(&mut self, rows: usize, cols: usize, condition: f64) -> Vec<f64> {
+        let rank = rows.min(cols);
+        let singular = |i: usize| match rank {
+            0 | 1 => 1.0,
+            _ => condition.powf(-(i as f64) / (rank - 1) as f64),
+        };
+
+        let u = self.unit_vector(rows);
+        let v = self.unit_vector(cols);
+
+        // `S * (I - 2vv^T) = S - 2(Sv)v^T`
+        let mut res = vec![0.0; rows * cols];
+        for i in 0..rank {
+            let sv = singular(i) * v[i];
+            for j in 0..cols {
+                res[i * cols + j] = -2.0 * sv * v[j];
+            }
+            res[i * cols + i] += singular(i);
+        }
+
+        // `(I - 2uu^T) * X = X - 2u(u^T X)`
+        let mut utx = vec![0.0; cols];
+        for i in 0..rows {
+            for j in 0..cols {
+                utx[j] += u[i] * res[i * cols + j];
+            }
+        }
+        for i in 0..rows {
+            for j in 0..cols {
+                res[i * cols + j] -= 2.0 * u[i] * utx[j];
+            }
+        }
+
+        res
+    }
+}
@@ -1,29 +1,163 @@
+use super::Element;
+
 /// Name of the kernel doing the multiplication (function name)
 pub const KERNEL_NAME: &str = "mul";
 
 /// tile size for various implementations
 pub const TILE: usize = 16;
-/// how much elements a thread is counting in [HARD] mode
+/// how much elements a thread is counting in [HARD] mode, unless the device was tuned
 pub const ELEM_PER_THREAD: usize = 2;
+/// width of the vectors a thread counts its elements with in [HARD] mode, unless the device was
+/// tuned
+pub const WIDTH: usize = 2;
+
+/// Parameters that [HARD_MUL] is built with, passed to the OpenCl compiler as `-D` options
+///
+/// Work groups compute `tile x tile` blocks of the result, every work item `elem_per_thread`
+/// elements of it in vectors of `width`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KernelConfig {
+    pub tile: usize,
+    pub elem_per_thread: usize,
+    pub width: usize,
+}
+
+impl Default for KernelConfig {
+    fn default() -> Self {
+        Self {
+            tile: TILE,
+            elem_per_thread: ELEM_PER_THREAD,
+            width: WIDTH,
+        }
+    }
+}
+
+impl KernelConfig {
+    /// Tiles that [KernelConfig::candidates] tries
+    const TILES: [usize; 3] = [8, 16, 32];
+    /// Elements per work item that [KernelConfig::candidates] tries
+    const ELEMS_PER_THREAD: [usize; 4] = [1, 2, 4, 8];
+    /// Vector widths that [KernelConfig::candidates] tries, OpenCl also has 8 and 16
+    const WIDTHS: [usize; 3] = [1, 2, 4];
+
+    /// Whether the kernel can be built with the config: every work item computes a share of the
+    /// rows of a nonempty tile in whole vectors, of a width that OpenCl has
+    ///
+    /// Devices also limit the [local_size](KernelConfig::local_size) and the
+    /// [local_memory](KernelConfig::local_memory) of configs
+    pub fn is_valid(&self) -> bool {
+        [1, 2, 4, 8, 16].contains(&self.width)
+            && self.tile > 0
+            && self.elem_per_thread > 0
+            && self.tile.is_multiple_of(self.elem_per_thread)
+            && self.elem_per_thread.is_multiple_of(self.width)
+    }
+
+    /// Every valid combination of a few common values, in increasing order
+    pub fn candidates() -> Vec<Self> {
+        let mut res = vec![];
+        for tile in Self::TILES {
+            for elem_per_thread in Self::ELEMS_PER_THREAD {
+                for width in Self::WIDTHS {
+                    let config = Self {
+                        tile,
+                        elem_per_thread,
+                        width,
+                    };
+                    if config.is_valid() {
+                        res.push(config);
+                    }
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Work items in a work group
+    pub fn local_size(&self) -> usize {
+        self.tile * self.tile / self.elem_per_thread
+    }
+
+    /// Local memory a work group needs, with accumulators of `acc_size` bytes
+    pub fn local_memory(&self, acc_size: usize) -> usize {
+        2 * self.tile * self.tile * acc_size
+    }
+
+    /// Build options that define the parameters for [HARD_MUL]
+    pub fn defines(&self) -> String {
+        format!(
+            "-D TILE={} -D ELEM_PER_THREAD={} -D WIDTH={}",
+            self.tile, self.elem_per_thread, self.width
+        )
+    }
+}
+
+/// Returns `source` prefixed with the definitions the kernels need to work with elements of type `T`
+///
+/// Kernels use `ELEM` as the type elements are stored in and `ACC` as the type products are summed
+/// up in. Elements are read with `LOAD(p, i)` and written with `STORE(p, i, v)`
+///
+/// All kernels share the same signature, see [GEMM_DEFINES] for the helpers built on top of it
+pub fn program_source<T: Element>(source: &str) -> String {
+    let mut res = String::new();
+
+    if let Some(extension) = T::CL_EXTENSION {
+        res += &format!("#pragma OPENCL EXTENSION {extension} : enable\n");
+    }
+
+    let acc = <T::Acc as Element>::CL_TYPE;
+
+    res += &format!("#define ELEM {}\n", T::CL_TYPE);
+    res += &format!("#define ACC {acc}\n");
+    res += &format!("#define LOAD(p, i) {}\n", T::CL_LOAD);
+    res += &format!("#define STORE(p, i, v) {}\n", T::CL_STORE);
+    res += GEMM_DEFINES;
+    res += source;
+
+    res
+}
+
+/// Helpers for kernels computing `m3 = alpha * op(m1) * op(m2) + beta * m3`
+///
+/// Every kernel takes `(m1, m2, m3, n, m, k, ta, tb, alpha, beta, sa, sb, sc)`, where `op(m1)` is
+/// `m x k`, `op(m2)` is `k x n` and `ta`, `tb` tell whether `m1` and `m2` are stored transposed.
+/// Batches are spread over the third dimension of the launch, matrix `get_global_id(2)` of each
+/// buffer starts at that index times `sa`, `sb` and `sc`, kernels pick it with `BATCH()`.
+/// `A(row, col)` and `B(row, col)` read elements of `op(m1)` and `op(m2)`, `C(row, col)` is the
+/// index of an element of `m3` and `RESULT(idx, v)` is the value that should be stored at `m3[idx]`
+/// given the accumulated product `v`. Offsets and indices are `uint`, buffers can not hold more
+/// than [u32::MAX] elements
+pub const GEMM_DEFINES: &str = r#"
+#define BATCH() uint oa = get_global_id(2) * sa; uint ob = get_global_id(2) * sb; \
+                uint oc = get_global_id(2) * sc
+#define A(row, col) (ta ? LOAD(m1, oa + (col) * m + (row)) : LOAD(m1, oa + (row) * k + (col)))
+#define B(row, col) (tb ? LOAD(m2, ob + (col) * k + (row)) : LOAD(m2, ob + (row) * n + (col)))
+#define C(row, col) (oc + (row) * n + (col))
+#define RESULT(idx, v) (beta == 0 ? alpha * (v) : alpha * (v) + beta * LOAD(m3, idx))
+"#;
 
 /// Source opencl code for easy multiplication
 pub const EASY_SOURCE: &str = r#"
-void kernel mul(global const float* m1, global const float* m2, 
-                              global float* m3, const uint n, const uint m, const uint k) {
+void kernel mul(global const ELEM* m1, global const ELEM* m2, 
+                              global ELEM* m3, const uint n, const uint m, const uint k,
+                              const uint ta, const uint tb, const ACC alpha, const ACC beta,
+                              const uint sa, const uint sb, const uint sc) {
+    BATCH();
     uint i = get_global_id(0);
     uint j = get_global_id(1);
-    float sum = 0.0f;
+    ACC sum = 0;
     for (uint w = 0; w < k; w++) {
-        sum += m1[j * k + w] * m2[w * n + i];
+        sum += A(j, w) * B(w, i);
     }
-    m3[j * n + i] = sum;
+    STORE(m3, C(j, i), RESULT(C(j, i), sum));
 }"#;
 
-/// Source opencl code for medium multiplication
+/// Source opencl code for medium multiplication, built with `TILE` defined as [TILE]
 pub const MEDIUM_MUL: &str = r#"
-#define TILE 16
-
-kernel void mul(global float* m1, global float* m2, global float* m3, uint n, uint m, uint k) {
+kernel void mul(global ELEM* m1, global ELEM* m2, global ELEM* m3, uint n, uint m, uint k,
+                uint ta, uint tb, ACC alpha, ACC beta, uint sa, uint sb, uint sc) {
+    BATCH();
 
     uint i = get_global_id(0);
     uint j = get_global_id(1);
@@ -31,16 +165,16 @@ kernel void mul(global float* m1, global float* m2, global float* m3, uint n, ui
     uint li = get_local_id(0);
     uint lj = get_local_id(1);
 
-    local float la[TILE][TILE];
-    local float lb[TILE][TILE];
+    local ACC la[TILE][TILE];
+    local ACC lb[TILE][TILE];
 
-    float sum = 0.0f;
+    ACC sum = 0;
     uint iter = k / TILE;
     for (uint w = 0; w < iter; w++) {
         uint trow = TILE * w + li;
         uint tcol = TILE * w + lj;
-        la[lj][li] = m1[j * k + trow];
-        lb[lj][li] = m2[tcol * n + i];
+        la[lj][li] = A(j, trow);
+        lb[lj][li] = B(tcol, i);
 
         barrier(CLK_LOCAL_MEM_FENCE);
 
@@ -51,49 +185,76 @@ kernel void mul(global float* m1, global float* m2, global float* m3, uint n, ui
         barrier(CLK_LOCAL_MEM_FENCE);
     }
 
-    m3[j * n + i] = sum;
+    STORE(m3, C(j, i), RESULT(C(j, i), sum));
 }"#;
 
 /// Source opencl code for hard multiplication
+///
+/// Built with `TILE`, `ELEM_PER_THREAD` and `WIDTH` defined, see [KernelConfig]. Every work item
+/// computes `ELEM_PER_THREAD` consecutive rows of a column of a tile, `WIDTH` of them at a time
 pub const HARD_MUL: &str = r#"
-#define TILE 16
-#define ELEM_PER_THREAD 2
+// rows of a tile that the work items of a group load at once
+#define NEW_TILE_SIZE (TILE / ELEM_PER_THREAD)
 
-// TILE / ELEM_PER_THREAD
-#define NEW_TILE_SIZE 8
+#define CAT_(a, b) a##b
+#define CAT(a, b) CAT_(a, b)
 
-kernel void mul(const global float* m1, const global float* m2, global float* m3, int n, int m, int k) {
+#if WIDTH == 1
+#define ACCV ACC
+#define VLOAD(i, p) ((p)[i])
+#define VSTORE(v, i, p) ((p)[i] = (v))
+#else
+#define ACCV CAT(ACC, WIDTH)
+#define VLOAD CAT(vload, WIDTH)
+#define VSTORE CAT(vstore, WIDTH)
+#endif
+
+kernel void mul(const global ELEM* m1, const global ELEM* m2, global ELEM* m3, uint n, uint m, uint k,
+                uint ta, uint tb, ACC alpha, ACC beta, uint sa, uint sb, uint sc) {
+    BATCH();
     uint li = get_local_id(0);
     uint lj = get_local_id(1);
 
     uint i = TILE * get_group_id(0) + li;
-    uint j = TILE * get_group_id(1) + lj;
+    uint j = TILE * get_group_id(1);
+
+    local ACC la[TILE][TILE];
+    // transposed, so that the rows of a work item are next to each other
+    local ACC lb[TILE][TILE];
 
-    local float la[TILE][TILE];
-    local float lb[TILE][TILE];
-    
-    float2 acc = (float2)(0.0f);
+    ACCV acc[ELEM_PER_THREAD / WIDTH];
+    for (uint w = 0; w < ELEM_PER_THREAD / WIDTH; w++) {
+        acc[w] = (ACCV)(0);
+    }
 
     uint iter = k / TILE;
     for (uint t = 0; t < iter; t++) {
-        uint trow = TILE * t + li;
-        uint tcol = TILE * t + lj;
-
         for (uint w = 0; w < ELEM_PER_THREAD; w++) {
-            la[lj + w * NEW_TILE_SIZE][li] = m2[(tcol + w * NEW_TILE_SIZE) * n + i];
-            lb[lj + w * NEW_TILE_SIZE][li] = m1[(j + w * NEW_TILE_SIZE) * k + trow];
+            uint row = lj + w * NEW_TILE_SIZE;
+            la[row][li] = B(TILE * t + row, i);
+            lb[li][row] = A(j + row, TILE * t + li);
         }
-        
+
         barrier(CLK_LOCAL_MEM_FENCE);
- 
+
         for (uint kk = 0; kk < TILE; kk++) {
-            acc += (float2)(la[kk][li]) * (float2)(lb[lj][kk], lb[lj + NEW_TILE_SIZE][kk]);
+            ACCV b = (ACCV)(la[kk][li]);
+            for (uint w = 0; w < ELEM_PER_THREAD / WIDTH; w++) {
+                acc[w] += VLOAD(w, &lb[kk][lj * ELEM_PER_THREAD]) * b;
+            }
         }
- 
+
         barrier(CLK_LOCAL_MEM_FENCE);
     }
 
-    m3[j * n + i] = acc.s0;
-    m3[(j + NEW_TILE_SIZE) * n + i] = acc.s1;
+    ACC res[ELEM_PER_THREAD];
+    for (uint w = 0; w < ELEM_PER_THREAD / WIDTH; w++) {
+        VSTORE(acc[w], w, res);
+    }
+
+    for (uint w = 0; w < ELEM_PER_THREAD; w++) {
+        uint row = j + lj * ELEM_PER_THREAD + w;
+        STORE(m3, C(row, i), RESULT(C(row, i), res[w]));
+    }
 }
 "#;
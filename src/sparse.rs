@@ -0,0 +1,47 @@
+use super::Element;
+use super::Matrix;
+
+/// Matrix that only stores its non zero elements, as `(row, col, value)` triples
+pub struct SparseMatrix<T = f32> {
+    /// Count of rows of the Matrix
+    pub rows: usize,
+    /// Count of columns of the Matrix
+    pub cols: usize,
+    /// Non zero elements in no particular order, values at the same position are summed up
+    pub entries: Vec<(usize, usize, T)>,
+}
+
+impl<T: Element> SparseMatrix<T> {
+    /// Creates a dense copy, every element that is not in `entries` is zero
+    ///
+    /// Can panic if given bad arguments (entries out of bounds)
+    pub fn to_dense(&self) -> Matrix<T> {
+        let mut res = Matrix::<T>::create_empty(self.rows, self.cols);
+
+        for &(row, col, value) in &self.entries {
+            res.set(row, col, res.get(row, col).wrapping_add(value));
+        }
+
+        res
+    }
+
+    /// Creates a sparse copy of `matrix` without its zeroes
+    pub fn from_dense(matrix: &Matrix<T>) -> Self {
+        let mut entries = vec![];
+
+        for row in 0..matrix.rows {
+            for col in 0..matrix.cols {
+                let value = matrix.get(row, col);
+                if value != T::default() {
+                    entries.push((row, col, value));
+                }
+            }
+        }
+
+        Self {
+            rows: matrix.rows,
+            cols: matrix.cols,
+            entries,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use memmap2::{MmapMut, MmapOptions};
+
+use super::Element;
+
+/// Elements of a [Matrix](crate::Matrix), either owned or memory mapped from a file
+///
+/// Dereferences to a slice either way. Mapped elements are copy on write, changing them only
+/// copies the touched pages and never writes to the file
+pub struct Storage<T> {
+    inner: Inner<T>,
+}
+
+enum Inner<T> {
+    Owned(Vec<T>),
+    Mapped {
+        map: MmapMut,
+        len: usize,
+        _marker: PhantomData<T>,
+    },
+}
+
+impl<T: Element> Storage<T> {
+    /// Maps `len` elements of `file` starting at byte `offset`
+    ///
+    /// Elements are used as they are in the file, in the byte order of the host
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped, see [MmapOptions::map_copy]
+    pub(crate) unsafe fn map(file: &File, offset: u64, len: usize) -> io::Result<Self> {
+        let size = len * std::mem::size_of::<T>();
+        if size == 0 {
+            return Ok(Self::from(vec![]));
+        }
+
+        // mapping past the end of the file would crash on the first access
+        if file.metadata()?.len() < offset + size as u64 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let map = MmapOptions::new().offset(offset).len(size).map_copy(file)?;
+        if !(map.as_ptr() as usize).is_multiple_of(std::mem::align_of::<T>()) {
+            let msg = "mapped elements are not aligned";
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        }
+
+        Ok(Self {
+            inner: Inner::Mapped {
+                map,
+                len,
+                _marker: PhantomData,
+            },
+        })
+    }
+
+    /// Whether the elements are memory mapped from a file
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.inner, Inner::Mapped { .. })
+    }
+
+    /// Owned elements, mapped ones are copied
+    pub fn into_vec(self) -> Vec<T> {
+        match self.inner {
+            Inner::Owned(data) => data,
+            Inner::Mapped { .. } => self.to_vec(),
+        }
+    }
+}
+
+impl<T: Element> Deref for Storage<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match &self.inner {
+            Inner::Owned(data) => data,
+            // safe because the mapping is aligned for `T`, see [Storage::map], and elements are
+            // plain numbers, see [Element]
+            Inner::Mapped { map, len, .. } => unsafe {
+                std::slice::from_raw_parts(map.as_ptr().cast(), *len)
+            },
+        }
+    }
+}
+
+impl<T: Element> DerefMut for Storage<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        match &mut self.inner {
+            Inner::Owned(data) => data,
+            // safe for the same reasons as in `deref`
+            Inner::Mapped { map, len, .. } => unsafe {
+                std::slice::from_raw_parts_mut(map.as_mut_ptr().cast(), *len)
+            },
+        }
+    }
+}
+
+impl<T> From<Vec<T>> for Storage<T> {
+    fn from(data: Vec<T>) -> Self {
+        Self {
+            inner: Inner::Owned(data),
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Storage<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
@@ -0,0 +1,81 @@
+use std::num::NonZeroUsize;
+
+use matmul::{DeviceSelector, Element, KernelConfig, Options};
+
+/// Options of the tune command
+#[derive(Debug, clap::Args)]
+pub struct TuneArgs {
+    /// Size of the square matrices that every config multiplies
+    #[arg(short, long, default_value_t = 1024)]
+    pub size: usize,
+    /// Timed runs of every config
+    #[arg(short, long, default_value = "5")]
+    pub repetitions: NonZeroUsize,
+    /// Print the timings without storing the best config
+    #[arg(long)]
+    pub dry_run: bool,
+    #[command(flatten)]
+    pub device: DeviceSelector,
+}
+
+/// Times every kernel config of the hard implementation on the selected device, prints the
+/// timings and stores the fastest config in the tuning database of `options`
+pub fn run<T: Element>(args: &TuneArgs, options: &Options) {
+    let tuning = match matmul::tune::<T>(&args.device, args.size, args.repetitions, options) {
+        Ok(res) => res,
+        Err(e) => {
+            eprintln!("unable to tune kernels: {}", e);
+            return;
+        }
+    };
+
+    println!("Device: {}", tuning.device);
+    println!("Driver version: {}", tuning.driver_version);
+    println!();
+    println!("tile  elem_per_thread  width  median_ms     gflops");
+
+    let flops = 2.0 * (args.size as f64).powi(3);
+    for res in &tuning.results {
+        let KernelConfig {
+            tile,
+            elem_per_thread,
+            width,
+        } = res.config;
+        let config = format!("{tile:>4}  {elem_per_thread:>15}  {width:>5}");
+
+        match &res.time {
+            Ok(time) => println!("{config}  {time:>9.3}  {:>9.2}", flops / time / 1e6),
+            Err(e) => println!("{config}  {e}"),
+        }
+    }
+
+    let Some(best) = tuning.best() else {
+        eprintln!("no kernel config works on {}", tuning.device);
+        return;
+    };
+    let KernelConfig {
+        tile,
+        elem_per_thread,
+        width,
+    } = best.config;
+
+    println!();
+    println!(
+        "Best: tile {}, {} elements per work item, vectors of {}",
+        tile, elem_per_thread, width
+    );
+
+    if args.dry_run {
+        return;
+    }
+
+    let Some(path) = options.tuning_database.path() else {
+        eprintln!("tuning database is disabled, the config was not stored");
+        return;
+    };
+
+    match tuning.save(&options.tuning_database) {
+        Ok(_) => println!("Stored in {}", path.display()),
+        Err(e) => eprintln!("unable to store kernel config: {}", e),
+    }
+}
@@ -0,0 +1,198 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use opencl3::device::Device;
+
+use super::sources::KernelConfig;
+use super::Result;
+
+/// Name of the database file in the cache directory
+const FILE_NAME: &str = "tuning.txt";
+
+/// Size of the largest accumulators, of `f64` and `i64`, that stored configs must fit the local
+/// memory of their device with
+pub(crate) const MAX_ACC_SIZE: usize = 8;
+
+/// First line of the database file
+const HEADER: &str = "# tile elem_per_thread width\tdriver version\tdevice";
+
+/// On disk database of the best [KernelConfig] of every device, as found by
+/// [tune](crate::tune)
+///
+/// Configs are keyed by the device name and driver version, a driver update may change what is
+/// fastest, so devices are tuned again after one. The file has a line per device with the config,
+/// the driver version and the device name separated by tabs
+#[derive(Clone, Debug)]
+pub struct TuningDatabase {
+    /// File with the configs, `None` if the database is disabled
+    path: Option<PathBuf>,
+}
+
+/// Config of a single device in a [TuningDatabase]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TuningEntry {
+    pub device: String,
+    pub driver_version: String,
+    pub config: KernelConfig,
+}
+
+impl Default for TuningDatabase {
+    /// Database in the directory of the default [ProgramCache](crate::ProgramCache), binaries
+    /// and configs are cleared separately
+    ///
+    /// The database is disabled if there is no such directory
+    fn default() -> Self {
+        Self {
+            path: super::cache::default_dir().map(|dir| dir.join(FILE_NAME)),
+        }
+    }
+}
+
+impl TuningDatabase {
+    /// Database in the provided file, it is created when the first config is stored
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+        }
+    }
+
+    /// Database that has no configs and never stores any, devices use the default config
+    pub fn disabled() -> Self {
+        Self { path: None }
+    }
+
+    /// File with the configs, `None` if the database is disabled
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Every stored config, lines that are not valid entries are skipped
+    pub fn entries(&self) -> Result<Vec<TuningEntry>> {
+        let Some(path) = &self.path else {
+            return Ok(vec![]);
+        };
+
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let res = content
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(parse_entry)
+            .collect();
+
+        Ok(res)
+    }
+
+    /// Config stored for the device, if any
+    pub fn get(&self, device: &str, driver_version: &str) -> Result<Option<KernelConfig>> {
+        let res = self
+            .entries()?
+            .into_iter()
+            .find(|entry| entry.device == device && entry.driver_version == driver_version)
+            .map(|entry| entry.config);
+
+        Ok(res)
+    }
+
+    /// Stores `config` for the device, replacing the previous one, does nothing if the database is
+    /// disabled
+    ///
+    /// Fails if the config is not [valid](KernelConfig::is_valid)
+    pub fn set(&self, device: &str, driver_version: &str, config: KernelConfig) -> Result<()> {
+        if !config.is_valid() {
+            let msg = format!("invalid kernel config {config:?}");
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into());
+        }
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut entries = self.entries()?;
+        entries.retain(|entry| entry.device != device || entry.driver_version != driver_version);
+        entries.push(TuningEntry {
+            device: device.to_string(),
+            driver_version: driver_version.to_string(),
+            config,
+        });
+
+        let mut content = format!("{HEADER}\n");
+        for TuningEntry {
+            device,
+            driver_version,
+            config,
+        } in &entries
+        {
+            let KernelConfig {
+                tile,
+                elem_per_thread,
+                width,
+            } = config;
+            content += &format!("{tile} {elem_per_thread} {width}\t{driver_version}\t{device}\n");
+        }
+
+        super::cache::write_atomically(path, content.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Config of `device`, the default one if it was never tuned or if the stored one exceeds the
+    /// limits of the device
+    ///
+    /// A database that can not be read is treated as empty, it should never fail a multiplication
+    pub(crate) fn config(&self, device: &Device) -> KernelConfig {
+        let (Ok(name), Ok(driver_version)) = (device.name(), device.driver_version()) else {
+            return KernelConfig::default();
+        };
+
+        self.get(&name, &driver_version)
+            .ok()
+            .flatten()
+            .filter(|&config| fits(config, device, MAX_ACC_SIZE))
+            .unwrap_or_default()
+    }
+}
+
+/// Whether `device` can run the kernel built with `config` on accumulators of `acc_size` bytes,
+/// not if its limits can not be queried
+pub(crate) fn fits(config: KernelConfig, device: &Device, acc_size: usize) -> bool {
+    let (Ok(max_local_size), Ok(max_local_memory)) =
+        (device.max_work_group_size(), device.local_mem_size())
+    else {
+        return false;
+    };
+
+    config.local_size() <= max_local_size
+        && config.local_memory(acc_size) as u64 <= max_local_memory
+}
+
+/// Entry of a line of the database, `None` if the line is not valid
+fn parse_entry(line: &str) -> Option<TuningEntry> {
+    let mut parts = line.splitn(3, '\t');
+    let (config, driver_version, device) = (parts.next()?, parts.next()?, parts.next()?);
+
+    let values = config
+        .split(' ')
+        .map(|value| value.parse().ok())
+        .collect::<Option<Vec<usize>>>()?;
+    let [tile, elem_per_thread, width] = values[..] else {
+        return None;
+    };
+
+    let config = KernelConfig {
+        tile,
+        elem_per_thread,
+        width,
+    };
+
+    config.is_valid().then(|| TuningEntry {
+        device: device.to_string(),
+        driver_version: driver_version.to_string(),
+        config,
+    })
+}
@@ -0,0 +1,137 @@
+use std::process;
+
+use clap::ValueEnum;
+use matmul::implementation_with_options;
+use matmul::{Accuracy, Element, Mode, Options};
+use matmul::{Reference, Tolerance};
+
+use crate::bench::Size;
+use crate::Args;
+
+/// Options of the verify command
+#[derive(Debug, clap::Args)]
+pub struct VerifyArgs {
+    /// Size of the random operands, `n` for square matrices or `MxKxN` for an `M x K` by `K x N`
+    /// product, ignored if `--a` and `--b` are given
+    #[arg(short, long, default_value = "512")]
+    pub size: Size,
+    /// Seed of the random operands, a random one is used and printed by default
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Product that the result is compared against
+    #[arg(short, long, value_enum, default_value_t)]
+    pub reference: Reference,
+    /// Largest absolute difference of two elements
+    #[arg(long)]
+    pub abs_tol: Option<f64>,
+    /// Largest difference of two elements relative to the expected one
+    #[arg(long)]
+    pub rel_tol: Option<f64>,
+    /// Largest distance of two elements in representable values of `--dtype`
+    #[arg(long)]
+    pub ulps: Option<u64>,
+    /// Largest Frobenius norm of the difference relative to the one of the expected result,
+    /// `4 * sqrt(k)` epsilons of `--dtype` by default
+    #[arg(long)]
+    pub frobenius_tol: Option<f64>,
+    /// Implementation that is verified
+    #[command(subcommand)]
+    pub mode: Mode,
+}
+
+/// Multiplies random operands, or the ones from `--a` and `--b`, with the chosen implementation
+/// and with the reference, and exits with an error unless the difference is within the tolerances
+pub fn run<T: Element>(cli: &Args, args: &VerifyArgs, options: &Options) {
+    let operands = if cli.a.is_some() {
+        crate::read_operands::<T>(cli)
+    } else {
+        let seed = args.seed.unwrap_or_else(|| {
+            let seed = rand::random();
+            println!("Seed: {}", seed);
+            seed
+        });
+
+        let mut generator = matmul::Generator::new(seed);
+        let Size { m, k, n } = args.size;
+        Ok((
+            crate::bench::random(&mut generator, m, k),
+            crate::bench::random(&mut generator, k, n),
+        ))
+    };
+
+    let (a, b) = match operands {
+        Ok(res) => res,
+        Err(e) => {
+            eprintln!("unable to parse given input file: {}", e);
+            return;
+        }
+    };
+
+    let mut multiplier = match implementation_with_options::<T>(args.mode.clone(), options) {
+        Ok(res) => res,
+        Err(e) => {
+            eprintln!("unable to create multiplier: {}", e);
+            return;
+        }
+    };
+
+    match multiplier.info() {
+        Ok(info) => crate::print_info(&info),
+        Err(e) => {
+            eprintln!("unable to get multiplier info, {}", e);
+            return;
+        }
+    }
+
+    let defaults = Tolerance::for_product::<T>(a.cols);
+    let tolerance = Tolerance {
+        max_abs_error: args.abs_tol,
+        max_rel_error: args.rel_tol,
+        max_ulps: args.ulps,
+        rel_error: args.frobenius_tol.or(defaults.rel_error),
+    };
+
+    let res = match matmul::verify(multiplier.as_mut(), &a, &b, args.reference, tolerance) {
+        Ok(res) => res,
+        Err(e) => {
+            eprintln!("unable to verify multiplication: {}", e);
+            return;
+        }
+    };
+
+    // unwrap is safe because no variant is skipped
+    let reference = args.reference.to_possible_value().unwrap();
+    println!("Reference: {}", reference.get_name());
+    print_accuracy(&res.accuracy);
+
+    let violations = tolerance.violations(&res.accuracy);
+    if violations.is_empty() {
+        println!("Passed");
+        return;
+    }
+
+    for (name, value, bound) in violations {
+        // ulps are whole numbers
+        if value.fract() == 0.0 && bound.fract() == 0.0 {
+            eprintln!("Failed: {} {} exceeds {}", name, value, bound);
+        } else {
+            eprintln!("Failed: {} {:e} exceeds {:e}", name, value, bound);
+        }
+    }
+    process::exit(1);
+}
+
+/// Prints every metric of `accuracy` and where the largest difference is, if there is one
+pub fn print_accuracy(accuracy: &Accuracy) {
+    println!("Max absolute error: {:e}", accuracy.max_abs_error);
+    println!("Max relative error: {:e}", accuracy.max_rel_error);
+    println!("Max ulp distance: {}", accuracy.max_ulps);
+    println!("Relative error: {:e}", accuracy.rel_error);
+
+    if let Some(worst) = accuracy.worst.filter(|_| accuracy.max_abs_error > 0.0) {
+        println!(
+            "Worst element: row {}, column {}, {} instead of {}",
+            worst.row, worst.col, worst.actual, worst.expected
+        );
+    }
+}